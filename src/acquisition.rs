@@ -0,0 +1,376 @@
+// Adquisición desde la fuente de datos elegida.
+//
+// Lee los registros del banco, aplica calibración, tara y termopares, los
+// escribe en el CSV y guarda la sesión completa en el estado compartido.
+// Todo lo recibido queda además en la captura cruda junto al CSV. La usan
+// el monitoreo en vivo y los comandos `apogeo record` y `apogeo reprocess`.
+
+use crate::binary;
+use crate::calibration::{CalibrationProfile, UNCALIBRATED};
+use crate::capture::{self, CaptureWriter};
+use crate::clock::{ClockStats, ClockSync};
+use crate::dataset::{
+    self, format_timestamp, format_utc, DataPoint, Metadata, PROPELLANT_MASS_TAG,
+};
+use crate::framing::{Decoder, FrameStats, WireFormat};
+use crate::history::History;
+use crate::schema::Schema;
+use crate::source::{DataSource, LinkState, SourceConfig, SourceKind, SourceStatus};
+use chrono::Utc;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Lecturas crudas que se promedian al capturar un punto de calibración
+const RAW_CAPTURE_LEN: usize = 50;
+
+pub struct AcquisitionConfig {
+    pub source: SourceConfig,
+    pub wire_format: WireFormat,
+    pub schema: Schema,
+    pub file_path: String,
+    pub calibration: Option<CalibrationProfile>,
+    pub propellant_mass: f64, // kg; 0 si no se conoce
+    pub tare_seconds: f64,
+    pub start_time: Instant,
+    pub operator: String,
+    pub notes: String,
+}
+
+// Estado que el hilo de adquisición comparte con quien lo controla
+#[derive(Clone)]
+pub struct SharedState {
+    pub last_data: Arc<Mutex<String>>,
+    pub running: Arc<Mutex<bool>>,
+    pub data_points: Arc<Mutex<History>>,
+    pub frame_stats: Arc<Mutex<FrameStats>>,
+    pub source_status: Arc<Mutex<SourceStatus>>,
+    pub clock_stats: Arc<Mutex<Option<ClockStats>>>, // solo con reloj de la placa
+    pub raw_thrust: Arc<Mutex<Vec<f64>>>,
+    pub tare_request: Arc<Mutex<Option<f64>>>,
+    pub tare_offset: Arc<Mutex<f64>>,
+    pub markers: Arc<Mutex<Vec<String>>>, // marcas del operador por anotar
+}
+
+impl Default for SharedState {
+    fn default() -> Self {
+        Self {
+            last_data: Arc::new(Mutex::new("Esperando datos...".to_string())),
+            running: Arc::new(Mutex::new(true)),
+            data_points: Arc::new(Mutex::new(History::default())),
+            frame_stats: Arc::new(Mutex::new(FrameStats::default())),
+            source_status: Arc::new(Mutex::new(SourceStatus::default())),
+            clock_stats: Arc::new(Mutex::new(None)),
+            raw_thrust: Arc::new(Mutex::new(Vec::new())),
+            tare_request: Arc::new(Mutex::new(None)),
+            tare_offset: Arc::new(Mutex::new(0.0)),
+            markers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+// Corre hasta que `running` pasa a false o la fuente termina. Un error de la
+// fuente o del archivo también queda en `last_data`
+pub fn run(config: &AcquisitionConfig, shared: &SharedState) -> Result<(), String> {
+    let SharedState {
+        last_data,
+        running,
+        data_points,
+        frame_stats,
+        source_status,
+        clock_stats,
+        raw_thrust,
+        tare_request,
+        tare_offset,
+        markers,
+    } = shared;
+    let schema = &config.schema;
+    let calibration = &config.calibration;
+    let start_time = config.start_time;
+    let fail = |message: String| {
+        *last_data.lock().unwrap() = message.clone();
+        message
+    };
+
+    // Reprocesar no debe continuar el registro de la captura que se lee
+    let capture_path = capture::capture_path(&config.file_path);
+    if config.source.kind == SourceKind::Capture && config.source.capture_path == capture_path {
+        return Err(fail(
+            "El registro reprocesado no puede ir junto a la captura original".to_string(),
+        ));
+    }
+
+    // Un esquema que no entra en un paquete binario no se podría decodificar
+    if config.wire_format == WireFormat::Binary {
+        let fields = schema.channels.len() + usize::from(schema.device_clock.is_some());
+        binary::check_fields(fields).map_err(fail)?;
+    }
+
+    let mut source = config
+        .source
+        .open(schema, config.wire_format)
+        .map_err(fail)?;
+
+    let file = match OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&config.file_path)
+    {
+        Ok(file) => file,
+        Err(e) => return Err(fail(format!("Error al abrir el archivo: {}", e))),
+    };
+
+    let mut file = file;
+
+    // Cada sesión empieza con su cabecera de metadatos, para saber semanas
+    // después de qué ensayo salió el archivo, seguida del encabezado de
+    // columnas. Si el archivo ya tenía sesiones, la lectura toma la última
+    // (ver `dataset::read_csv`). La hora de inicio corresponde
+    // a `start_time` (o al inicio original, al reprocesar), así la columna
+    // UTC coincide con la columna Tiempo
+    let started_at = source.started_at().unwrap_or_else(|| {
+        Utc::now()
+            - chrono::Duration::from_std(start_time.elapsed())
+                .unwrap_or_else(|_| chrono::Duration::zero())
+    });
+    // Hora del host desde Iniciar; al reprocesar, la de la recepción original
+    let now =
+        |source: &dyn DataSource| source.received_at().unwrap_or_else(|| start_time.elapsed());
+    // Al reprocesar, el operador, las notas y la masa del ensayo original
+    // se conservan salvo que se indiquen otros
+    let (recorded, recorded_mass) = dataset::read_header(source.recorded_header());
+    let optional = |text: &str| (!text.trim().is_empty()).then(|| text.trim().to_string());
+    let metadata = Metadata {
+        started_at: Some(started_at),
+        host: Some(hostname()),
+        port: config.source.endpoint(),
+        baud_rate: (config.source.kind == SourceKind::Serial).then_some(config.source.baud_rate),
+        version: Some(format!("apogeo {}", env!("CARGO_PKG_VERSION"))),
+        calibration: Some(match &calibration {
+            Some(profile) => profile.describe(),
+            None => UNCALIBRATED.to_string(),
+        }),
+        operator: optional(&config.operator).or(recorded.operator),
+        notes: optional(&config.notes).or(recorded.notes),
+    };
+    let mut header = metadata.header_lines();
+    // Reprocesado con otra calibración: queda anotada también la original
+    if let Some(original) = recorded.calibration {
+        if metadata.calibration.as_ref() != Some(&original) {
+            header.push(format!("calibracion_original: {}", original));
+        }
+    }
+    header.push(format!("fuente: {}", config.source.describe()));
+    if let Some(clock) = schema.device_clock {
+        header.push(format!("reloj_placa: {}", clock.describe()));
+    }
+    let propellant_mass = Some(config.propellant_mass)
+        .filter(|&mass| mass > 0.0)
+        .or(recorded_mass);
+    if let Some(mass) = propellant_mass {
+        header.push(format!("{} {} kg", PROPELLANT_MASS_TAG, mass));
+    }
+    for line in &header {
+        let _ = writeln!(file, "# {}", line);
+    }
+    let _ = writeln!(file, "{}", schema.csv_header());
+
+    // Una captura que se reprocesa ya es la evidencia cruda: no se copia
+    let mut capture = match config.source.kind {
+        SourceKind::Capture => None,
+        _ => Some(
+            CaptureWriter::create(&capture_path, started_at, config.wire_format, &header)
+                .map_err(fail)?,
+        ),
+    };
+
+    raw_thrust.lock().unwrap().clear();
+    let thrust_index = schema.thrust_index();
+    *tare_request.lock().unwrap() = None;
+    *tare_offset.lock().unwrap() = 0.0;
+    let mut offset = 0.0;
+    let mut tare_window = config.tare_seconds;
+
+    let mut data = last_data.lock().unwrap();
+    *data = "Conexión exitosa, esperando datos...".to_string();
+    drop(data);
+
+    // Los bytes se acumulan hasta completar un registro; una lectura
+    // puede traer fragmentos o varios registros a la vez
+    let mut decoder = Decoder::new(
+        config.wire_format,
+        schema.channels.len(),
+        schema.device_clock.is_some(),
+    );
+    let mut clock = schema.device_clock.map(ClockSync::new);
+    *clock_stats.lock().unwrap() = None;
+    let mut buf = [0; 256];
+    let mut result = Ok(());
+    *frame_stats.lock().unwrap() = FrameStats::default();
+    // El estado inicial también se anota en el primer ciclo
+    let mut link_state = LinkState::Connecting;
+
+    loop {
+        if !*running.lock().unwrap() {
+            break;
+        }
+
+        // Al reprocesar, la tara se vuelve a calcular sobre los datos
+        // reprocesados con la ventana original, así sigue siendo válida con
+        // otra calibración
+        if let Some(window) = source.recorded_tare() {
+            if let Some(average) = data_points.lock().unwrap().average(thrust_index, window) {
+                tare_window = window;
+                *tare_request.lock().unwrap() = Some(average);
+            }
+        }
+
+        // La tara se registra como evento para poder reproducirla
+        // en el análisis posterior
+        if let Some(delta) = tare_request.lock().unwrap().take() {
+            offset += delta;
+            *tare_offset.lock().unwrap() = offset;
+            let time = now(&*source);
+            if let Some(capture) = &mut capture {
+                let _ = capture.tare(time, tare_window);
+            }
+            let _ = writeln!(
+                file,
+                "# evento: tara | tiempo {} | offset {} N | ventana {} s",
+                format_timestamp(time),
+                offset,
+                tare_window
+            );
+        }
+
+        for marker in markers.lock().unwrap().drain(..) {
+            let _ = writeln!(
+                file,
+                "# evento: marca | tiempo {} | {}",
+                format_timestamp(now(&*source)),
+                marker
+            );
+        }
+
+        let read = source.read(&mut buf);
+
+        // Los cambios del enlace también quedan en el registro
+        let status = source.status();
+        if status.state != link_state {
+            link_state = status.state;
+            let _ = writeln!(
+                file,
+                "# evento: enlace | tiempo {} | {} | {}",
+                format_timestamp(now(&*source)),
+                link_state.label(),
+                if status.state == LinkState::Connected {
+                    &status.peer
+                } else {
+                    &status.last_error
+                }
+            );
+        }
+        *source_status.lock().unwrap() = status.clone();
+
+        let n = match read {
+            Ok(Some(n)) if n > 0 => n,
+            Ok(Some(_)) => continue,
+            Ok(None) => {
+                *last_data.lock().unwrap() = "La fuente de datos terminó".to_string();
+                break;
+            }
+            Err(e) => {
+                result = Err(fail(e));
+                break;
+            }
+        };
+
+        // Los bytes van a la captura antes de decodificarlos, así queda
+        // también lo que el decodificador descarte
+        let received_at = now(&*source);
+        if let Some(capture) = &mut capture {
+            let _ = capture.data(received_at, &buf[..n]);
+        }
+
+        for mut values in decoder.push(&buf[..n]) {
+            // Con reloj de la placa el tiempo sale del contador; la hora del
+            // host se conserva en su propia columna
+            let host_time = received_at.as_secs_f64();
+            let time = match &mut clock {
+                Some(clock) => {
+                    let counter = values.remove(0);
+                    let resets = clock.resets();
+                    let time = clock.update(counter, host_time);
+                    if clock.resets() != resets {
+                        let _ = writeln!(
+                            file,
+                            "# evento: reloj reanclado | tiempo {} | contador {}",
+                            format_timestamp(Duration::from_secs_f64(host_time)),
+                            counter
+                        );
+                    }
+                    time
+                }
+                None => host_time,
+            };
+
+            let raw = values[thrust_index];
+            let mut raw_samples = raw_thrust.lock().unwrap();
+            if raw_samples.len() >= RAW_CAPTURE_LEN {
+                raw_samples.remove(0);
+            }
+            raw_samples.push(raw);
+            drop(raw_samples);
+
+            if let Some(profile) = &calibration {
+                values[thrust_index] = profile.apply(raw);
+            }
+            values[thrust_index] -= offset;
+            schema.convert_thermocouples(&mut values);
+
+            let timestamp = format_timestamp(Duration::from_secs_f64(time.max(0.0)));
+            let utc = started_at + chrono::Duration::microseconds((time * 1e6) as i64);
+
+            let fields: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            let mut row = format!("{},{}", timestamp, fields.join(","));
+            if clock.is_some() {
+                row.push_str(&format!(",{:.6},{:.6}", time, host_time));
+            }
+            let _ = writeln!(file, "{},{}", row, format_utc(utc));
+            let mut data = last_data.lock().unwrap();
+            *data = fields.join(" | ");
+            drop(data);
+
+            data_points.lock().unwrap().push(DataPoint { time, values });
+        }
+
+        *frame_stats.lock().unwrap() = decoder.stats().clone();
+        if let Some(clock) = &clock {
+            *clock_stats.lock().unwrap() = Some(clock.stats());
+        }
+    }
+
+    // El resumen de ambos relojes queda al final de la sesión
+    if let Some(clock) = &clock {
+        let _ = writeln!(file, "# reloj: {}", clock.stats().describe());
+    }
+
+    // Lo escrito queda en disco aunque el proceso termine justo después
+    let _ = file.sync_all();
+    if let Some(capture) = &capture {
+        capture.sync();
+    }
+    result
+}
+
+// Nombre del equipo que registra, sin depender del sistema operativo
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "desconocido".to_string())
+}
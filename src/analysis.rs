@@ -0,0 +1,411 @@
+// Métricas de desempeño del motor a partir de la curva de empuje.
+
+use serde::Serialize;
+
+pub const G0: f64 = 9.80665; // gravedad estándar (m/s²)
+
+// Impulso específico (s): Isp = I / (m_prop · g0). Sin masa de propelente
+// no se puede calcular
+pub fn specific_impulse(total_impulse: f64, propellant_mass: f64) -> Option<f64> {
+    if propellant_mass > 0.0 {
+        Some(total_impulse / (propellant_mass * G0))
+    } else {
+        None
+    }
+}
+
+// Flujo másico promedio entregado (kg/s) durante el tiempo de combustión
+pub fn mass_flow(propellant_mass: f64, burn_time: f64) -> Option<f64> {
+    if propellant_mass > 0.0 && burn_time > 0.0 {
+        Some(propellant_mass / burn_time)
+    } else {
+        None
+    }
+}
+
+// Integral trapezoidal del empuje (N·s)
+pub fn impulse(times: &[f64], thrust: &[f64]) -> f64 {
+    times
+        .windows(2)
+        .zip(thrust.windows(2))
+        .map(|(t, f)| (f[0] + f[1]) / 2.0 * (t[1] - t[0]))
+        .sum()
+}
+
+// Umbrales de detección, en porcentaje del empuje máximo
+#[derive(Clone, Copy, Serialize)]
+pub struct BurnThresholds {
+    #[serde(rename = "ignicion")]
+    pub ignition: f64, // inicio de la combustión
+    #[serde(rename = "apagado")]
+    pub burnout: f64, // fin de la combustión
+    #[serde(rename = "accion")]
+    pub action: f64, // límites del tiempo de acción
+}
+
+impl Default for BurnThresholds {
+    // 5 % para la combustión, como en NFPA 1125; 10 % para la acción
+    fn default() -> Self {
+        Self {
+            ignition: 5.0,
+            burnout: 5.0,
+            action: 10.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct BurnMetrics {
+    #[serde(rename = "ignicion_s")]
+    pub ignition_time: f64,
+    #[serde(rename = "apagado_s")]
+    pub burnout_time: f64,
+    #[serde(rename = "pico_s")]
+    pub peak_time: f64,
+    #[serde(rename = "empuje_pico_n")]
+    pub peak_thrust: f64,
+    #[serde(rename = "tiempo_combustion_s")]
+    pub burn_time: f64, // entre ignición y apagado
+    #[serde(rename = "tiempo_accion_s")]
+    pub action_time: f64, // entre los cruces del umbral de acción
+    #[serde(rename = "tiempo_al_pico_s")]
+    pub time_to_peak: f64, // desde la ignición
+    #[serde(rename = "empuje_promedio_n")]
+    pub average_thrust: f64, // promedio durante la combustión
+    #[serde(rename = "impulso_ns")]
+    pub impulse: f64, // impulso solo durante la combustión
+}
+
+// Detecta la ignición y el apagado como el primer y el último cruce de los
+// umbrales; devuelve None si no hay empuje positivo
+pub fn detect_burn(
+    times: &[f64],
+    thrust: &[f64],
+    thresholds: &BurnThresholds,
+) -> Option<BurnMetrics> {
+    let (peak_index, peak_thrust) = thrust
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, f)| f.is_finite())
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if peak_thrust <= 0.0 {
+        return None;
+    }
+
+    let ignition_time = rising_crossing(times, thrust, peak_thrust * thresholds.ignition / 100.0)?;
+    let burnout_time = falling_crossing(times, thrust, peak_thrust * thresholds.burnout / 100.0)?;
+    let action_level = peak_thrust * thresholds.action / 100.0;
+    let action_time = falling_crossing(times, thrust, action_level)?
+        - rising_crossing(times, thrust, action_level)?;
+
+    let burn_time = burnout_time - ignition_time;
+    let impulse = impulse_between(times, thrust, ignition_time, burnout_time);
+    let peak_time = times[peak_index];
+
+    Some(BurnMetrics {
+        ignition_time,
+        burnout_time,
+        peak_time,
+        peak_thrust,
+        burn_time,
+        action_time,
+        time_to_peak: peak_time - ignition_time,
+        average_thrust: if burn_time > 0.0 {
+            impulse / burn_time
+        } else {
+            0.0
+        },
+        impulse,
+    })
+}
+
+// Instante interpolado en que el empuje alcanza `level` por primera vez
+fn rising_crossing(times: &[f64], thrust: &[f64], level: f64) -> Option<f64> {
+    let i = thrust.iter().position(|&f| f >= level)?;
+    if i == 0 {
+        return Some(times[0]);
+    }
+    Some(interpolate(
+        times[i - 1],
+        thrust[i - 1],
+        times[i],
+        thrust[i],
+        level,
+    ))
+}
+
+// Instante interpolado en que el empuje cae bajo `level` por última vez
+fn falling_crossing(times: &[f64], thrust: &[f64], level: f64) -> Option<f64> {
+    let i = thrust.iter().rposition(|&f| f >= level)?;
+    if i + 1 == thrust.len() {
+        return Some(times[i]);
+    }
+    Some(interpolate(
+        times[i],
+        thrust[i],
+        times[i + 1],
+        thrust[i + 1],
+        level,
+    ))
+}
+
+fn interpolate(t0: f64, f0: f64, t1: f64, f1: f64, level: f64) -> f64 {
+    if f1 == f0 {
+        return t0;
+    }
+    t0 + (level - f0) / (f1 - f0) * (t1 - t0)
+}
+
+// Integral trapezoidal recortada al intervalo [start, end]
+fn impulse_between(times: &[f64], thrust: &[f64], start: f64, end: f64) -> f64 {
+    let mut total = 0.0;
+    for i in 1..times.len() {
+        let (t0, t1) = (times[i - 1], times[i]);
+        let a = t0.max(start);
+        let b = t1.min(end);
+        if b <= a || t1 <= t0 {
+            continue;
+        }
+        let at = |t: f64| thrust[i - 1] + (thrust[i] - thrust[i - 1]) * (t - t0) / (t1 - t0);
+        total += (at(a) + at(b)) / 2.0 * (b - a);
+    }
+    total
+}
+
+// Clase de impulso NAR/TRA: cada letra duplica el impulso de la anterior,
+// con A entre 1,25 y 2,5 N·s
+pub struct MotorClass {
+    pub letter: String,
+    pub percent: f64, // posición del impulso dentro del rango de la clase
+}
+
+pub fn classify(total_impulse: f64) -> Option<MotorClass> {
+    if total_impulse.is_nan() || total_impulse <= 0.0 {
+        return None;
+    }
+
+    // k = 0 es la clase A; valores negativos son 1/2A, 1/4A y 1/8A
+    let k = (total_impulse / 2.5).log2().ceil().max(-3.0) as i32;
+    if k > 25 {
+        return None;
+    }
+    let upper = 2.5 * 2f64.powi(k);
+    let lower = if k == -3 { 0.0 } else { upper / 2.0 };
+    let letter = match k {
+        -3 => "1/8A".to_string(),
+        -2 => "1/4A".to_string(),
+        -1 => "1/2A".to_string(),
+        _ => ((b'A' + k as u8) as char).to_string(),
+    };
+
+    Some(MotorClass {
+        letter,
+        percent: (total_impulse - lower) / (upper - lower) * 100.0,
+    })
+}
+
+// Designación estándar, por ejemplo "H128-7": clase, empuje promedio en N
+// y retardo (se omite si está vacío)
+pub fn designation(total_impulse: f64, average_thrust: f64, delay: &str) -> Option<String> {
+    let class = classify(total_impulse)?;
+    let mut designation = format!("{}{}", class.letter, average_thrust.round() as i64);
+    if !delay.trim().is_empty() {
+        designation.push('-');
+        designation.push_str(delay.trim());
+    }
+    Some(designation)
+}
+
+// Impulso y duración del motor: los de la combustión si se detectó, si no
+// los de todo el archivo
+pub fn motor_impulse(burn: Option<BurnMetrics>, total_impulse: f64) -> f64 {
+    burn.map_or(total_impulse, |b| b.impulse)
+}
+
+pub fn motor_burn_time(burn: Option<BurnMetrics>, duration: f64) -> f64 {
+    burn.map_or(duration, |b| b.burn_time)
+}
+
+// Designación del motor con su clase NAR/TRA, p. ej. "H128-7". None si no
+// se puede calcular el empuje promedio (sin combustión ni duración)
+pub fn motor_designation(
+    burn: Option<BurnMetrics>,
+    total_impulse: f64,
+    duration: f64,
+    delay: &str,
+) -> Option<(String, MotorClass)> {
+    let impulse = motor_impulse(burn, total_impulse);
+    let average_thrust = match burn {
+        Some(burn) => burn.average_thrust,
+        None if duration > 0.0 => impulse / duration,
+        None => return None,
+    };
+    Some((
+        designation(impulse, average_thrust, delay)?,
+        classify(impulse)?,
+    ))
+}
+
+// Valor de una curva [tiempo, empuje] interpolado linealmente; None fuera
+// de su rango
+pub fn interpolate_curve(curve: &[[f64; 2]], time: f64) -> Option<f64> {
+    let first = curve.first()?;
+    let last = curve.last()?;
+    if time < first[0] || time > last[0] {
+        return None;
+    }
+    let i = curve.partition_point(|p| p[0] < time);
+    if i == 0 {
+        return Some(first[1]);
+    }
+    let [t0, f0] = curve[i - 1];
+    let [t1, f1] = curve[i];
+    if t1 == t0 {
+        return Some(f0);
+    }
+    Some(f0 + (f1 - f0) * (time - t0) / (t1 - t0))
+}
+
+// Diferencias entre una curva medida y una de referencia
+pub struct Deviation {
+    pub rmse: f64,                   // N, sobre las muestras que se superponen
+    pub peak_difference: f64,        // N, medido - referencia
+    pub peak_difference_pct: f64,    // % del pico de referencia
+    pub impulse_difference_pct: f64, // % del impulso de referencia
+}
+
+// Compara dos curvas [tiempo, empuje] con el tiempo contado desde la
+// ignición. El impulso de la referencia se integra solo entre la primera y
+// la última muestra medida, para que la comparación sirva también en vivo
+pub fn compare(measured: &[[f64; 2]], reference: &[[f64; 2]]) -> Option<Deviation> {
+    let mut deviation = RunningDeviation::default();
+    for &[time, thrust] in measured {
+        deviation.push(reference, time, thrust);
+    }
+    deviation.result(reference)
+}
+
+// Versión incremental de `compare`: las muestras se agregan a medida que
+// llegan y el resultado se obtiene sin volver a recorrer la sesión
+#[derive(Clone, Copy)]
+pub struct RunningDeviation {
+    squares: f64,   // suma de cuadrados contra la referencia
+    matched: usize, // muestras dentro del rango de la referencia
+    peak: f64,
+    impulse: f64,
+    first_time: Option<f64>,
+    last: Option<[f64; 2]>,
+}
+
+impl Default for RunningDeviation {
+    fn default() -> Self {
+        Self {
+            squares: 0.0,
+            matched: 0,
+            peak: f64::NEG_INFINITY,
+            impulse: 0.0,
+            first_time: None,
+            last: None,
+        }
+    }
+}
+
+impl RunningDeviation {
+    // Agrega una muestra medida; se ignoran las previas a la ignición y las
+    // que no tienen empuje
+    pub fn push(&mut self, reference: &[[f64; 2]], time: f64, thrust: f64) {
+        if time < 0.0 || !thrust.is_finite() {
+            return;
+        }
+        if let Some(r) = interpolate_curve(reference, time) {
+            self.squares += (thrust - r).powi(2);
+            self.matched += 1;
+        }
+        self.peak = self.peak.max(thrust);
+        if let Some([t0, f0]) = self.last {
+            self.impulse += (f0 + thrust) / 2.0 * (time - t0);
+        }
+        self.first_time.get_or_insert(time);
+        self.last = Some([time, thrust]);
+    }
+
+    pub fn result(&self, reference: &[[f64; 2]]) -> Option<Deviation> {
+        let end = self.last?[0];
+        if self.matched == 0 {
+            return None;
+        }
+        let rmse = (self.squares / self.matched as f64).sqrt();
+
+        let reference_peak = reference
+            .iter()
+            .map(|p| p[1])
+            .fold(f64::NEG_INFINITY, f64::max);
+        let peak_difference = self.peak - reference_peak;
+
+        // La referencia se integra en la misma ventana que las muestras
+        let (reference_times, reference_thrust): (Vec<f64>, Vec<f64>) =
+            reference.iter().map(|p| (p[0], p[1])).unzip();
+        let reference_impulse = impulse_between(
+            &reference_times,
+            &reference_thrust,
+            self.first_time.unwrap_or(end),
+            end,
+        );
+
+        let percent = |difference: f64, base: f64| {
+            if base != 0.0 {
+                difference / base * 100.0
+            } else {
+                f64::NAN
+            }
+        };
+
+        Some(Deviation {
+            rmse,
+            peak_difference,
+            peak_difference_pct: percent(peak_difference, reference_peak),
+            impulse_difference_pct: percent(self.impulse - reference_impulse, reference_impulse),
+        })
+    }
+}
+
+// Empuje máximo y promedio de una serie completa, como en el panel de
+// estadísticas
+pub fn peak(values: &[f64]) -> f64 {
+    values.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b))
+}
+
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+// Media y desviación estándar muestral; ignora los valores no finitos
+pub fn mean_std(values: &[f64]) -> Option<(f64, f64)> {
+    let values: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let std = if values.len() > 1 {
+        (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+    } else {
+        0.0
+    };
+    Some((mean, std))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn designation_needs_a_duration() {
+        assert!(motor_designation(None, 300.0, 0.0, "7").is_none());
+
+        let (designation, class) = motor_designation(None, 300.0, 2.5, "7").unwrap();
+        assert_eq!(designation, "H120-7");
+        assert_eq!(class.letter, "H");
+    }
+}
@@ -0,0 +1,224 @@
+// Protocolo binario de telemetría.
+//
+// Cada paquete tiene la forma:
+//
+//   0xAA 0x55 | secuencia (u16 LE) | largo (u8) | carga útil | CRC-16 (u16 LE)
+//
+// La carga útil son `largo / 4` valores f32 little-endian en el mismo orden
+// que los campos del formato ASCII. Si el esquema declara el reloj de la
+// placa, el primer valor es ese contador como u32 little-endian. El CRC es
+// CRC-16/CCITT-FALSE calculado sobre secuencia, largo y carga útil.
+
+use crate::framing::FrameStats;
+
+pub const SYNC: [u8; 2] = [0xAA, 0x55];
+const HEADER_LEN: usize = 5; // sync + secuencia + largo
+const CRC_LEN: usize = 2;
+const MAX_PAYLOAD: usize = 64;
+// Campos que entran en la carga útil, contando el reloj de la placa
+pub const MAX_FIELDS: usize = MAX_PAYLOAD / 4;
+
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            if crc & 0x8000 != 0 {
+                crc = (crc << 1) ^ 0x1021;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+// Error si un registro de `fields` campos no entra en un paquete
+pub fn check_fields(fields: usize) -> Result<(), String> {
+    if fields > MAX_FIELDS {
+        return Err(format!(
+            "El formato binario admite hasta {} campos por registro y el esquema tiene {}",
+            MAX_FIELDS, fields
+        ));
+    }
+    Ok(())
+}
+
+// Arma un paquete con los valores como f32, tal como los envía la placa
+pub fn encode(sequence: u16, counter: Option<u32>, values: &[f64]) -> Result<Vec<u8>, String> {
+    let fields = values.len() + usize::from(counter.is_some());
+    check_fields(fields)?;
+    let mut body = Vec::with_capacity(3 + fields * 4);
+    body.extend_from_slice(&sequence.to_le_bytes());
+    body.push((fields * 4) as u8);
+    if let Some(counter) = counter {
+        body.extend_from_slice(&counter.to_le_bytes());
+    }
+    for value in values {
+        body.extend_from_slice(&(*value as f32).to_le_bytes());
+    }
+
+    let mut packet = SYNC.to_vec();
+    packet.extend_from_slice(&body);
+    packet.extend_from_slice(&crc16(&body).to_le_bytes());
+    Ok(packet)
+}
+
+pub struct BinaryFramer {
+    buffer: Vec<u8>,
+    expected_fields: usize,
+    counter: bool, // el primer campo es el reloj de la placa (u32)
+    last_sequence: Option<u16>,
+    pub stats: FrameStats,
+}
+
+impl BinaryFramer {
+    pub fn new(expected_fields: usize, counter: bool) -> Self {
+        Self {
+            buffer: Vec::new(),
+            expected_fields,
+            counter,
+            last_sequence: None,
+            stats: FrameStats::default(),
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Vec<f64>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut records = Vec::new();
+
+        loop {
+            // Descartar todo lo que no sea el inicio de un paquete
+            match self.buffer.windows(2).position(|w| w == SYNC) {
+                Some(0) => {}
+                Some(pos) => {
+                    self.buffer.drain(..pos);
+                }
+                None => {
+                    // Conservar un posible primer byte de sync al final
+                    let keep = usize::from(self.buffer.last() == Some(&SYNC[0]));
+                    let len = self.buffer.len();
+                    self.buffer.drain(..len - keep);
+                    break;
+                }
+            }
+
+            if self.buffer.len() < HEADER_LEN {
+                break;
+            }
+
+            let payload_len = self.buffer[4] as usize;
+            if payload_len > MAX_PAYLOAD {
+                self.stats.oversize += 1;
+                self.buffer.drain(..1);
+                continue;
+            }
+
+            let packet_len = HEADER_LEN + payload_len + CRC_LEN;
+            if self.buffer.len() < packet_len {
+                break;
+            }
+
+            let body = &self.buffer[SYNC.len()..HEADER_LEN + payload_len];
+            let received_crc =
+                u16::from_le_bytes([self.buffer[packet_len - 2], self.buffer[packet_len - 1]]);
+            if crc16(body) != received_crc {
+                // El sync pudo ser un falso positivo dentro de datos; se
+                // reintenta desde el byte siguiente
+                self.stats.crc_errors += 1;
+                self.buffer.drain(..1);
+                continue;
+            }
+
+            let sequence = u16::from_le_bytes([self.buffer[2], self.buffer[3]]);
+            if let Some(last) = self.last_sequence {
+                let gap = sequence.wrapping_sub(last).wrapping_sub(1);
+                // Un salto enorme suele ser un reinicio de la placa
+                if gap < u16::MAX / 2 {
+                    self.stats.lost += gap as u64;
+                }
+            }
+            self.last_sequence = Some(sequence);
+
+            let payload = &self.buffer[HEADER_LEN..HEADER_LEN + payload_len];
+            if payload_len < self.expected_fields * 4 {
+                self.stats.truncated += 1;
+            } else if payload_len > self.expected_fields * 4 {
+                self.stats.malformed += 1;
+            } else {
+                let values: Vec<f64> = payload
+                    .chunks_exact(4)
+                    .enumerate()
+                    .map(|(i, c)| {
+                        let bytes = [c[0], c[1], c[2], c[3]];
+                        if i == 0 && self.counter {
+                            u32::from_le_bytes(bytes) as f64
+                        } else {
+                            f32::from_le_bytes(bytes) as f64
+                        }
+                    })
+                    .collect();
+                if values.iter().all(|v| v.is_finite()) {
+                    self.stats.frames += 1;
+                    records.push(values);
+                } else {
+                    self.stats.malformed += 1;
+                }
+            }
+
+            self.buffer.drain(..packet_len);
+        }
+
+        records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc_check_value() {
+        // Valor de control de CRC-16/CCITT-FALSE
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn resyncs_after_garbage() {
+        let mut framer = BinaryFramer::new(3, false);
+        let mut bytes = vec![0x00, 0xAA, 0x55, 0x01, 0x02, 0x0C, 0xFF];
+        bytes.extend(encode(7, None, &[1.0, 2.0, 3.0]).unwrap());
+        bytes.extend(encode(8, None, &[4.0, 5.0, 6.0]).unwrap());
+
+        // De a un byte, como puede llegar por el puerto
+        let records: Vec<Vec<f64>> = bytes.iter().flat_map(|&b| framer.push(&[b])).collect();
+
+        assert_eq!(records, [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        assert_eq!(framer.stats.frames, 2);
+        assert_eq!(framer.stats.crc_errors, 1);
+    }
+
+    #[test]
+    fn counts_lost_and_corrupt_packets() {
+        let mut framer = BinaryFramer::new(2, true);
+        let mut corrupt = encode(2, Some(20), &[2.0]).unwrap();
+        corrupt[6] ^= 0x01;
+        let mut bytes = encode(1, Some(10), &[1.0]).unwrap();
+        bytes.extend(corrupt);
+        bytes.extend(encode(4, Some(40), &[4.0]).unwrap());
+
+        let records = framer.push(&bytes);
+
+        // El contador viaja como u32, sin pasar por f32
+        assert_eq!(records, [[10.0, 1.0], [40.0, 4.0]]);
+        assert_eq!(framer.stats.crc_errors, 1);
+        assert_eq!(framer.stats.lost, 2);
+    }
+
+    #[test]
+    fn rejects_too_many_fields() {
+        assert!(encode(0, None, &[0.0; MAX_FIELDS]).is_ok());
+        assert!(encode(0, Some(0), &[0.0; MAX_FIELDS]).is_err());
+        assert!(check_fields(MAX_FIELDS + 1).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+// Calibración de la celda de carga.
+//
+// Un perfil convierte las cuentas crudas que envía la placa en newtons. Se
+// obtiene ajustando un polinomio por mínimos cuadrados a lecturas tomadas
+// con masas de referencia. Los perfiles se guardan con nombre en un TOML:
+//
+//   [[perfil]]
+//   nombre = "celda-50kg"
+//   centro = 1234.5
+//   escala = 1000.0
+//   coeficientes = [0.1, 9.8]
+//
+// El polinomio se evalúa sobre u = (crudo - centro) / escala para que el
+// ajuste no pierda precisión con cuentas grandes de un ADC de 24 bits.
+
+use crate::analysis::G0;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const MAX_DEGREE: usize = 3;
+
+// Lo que anota el registro cuando el empuje llega sin calibrar
+pub const UNCALIBRATED: &str = "ninguna";
+
+// Nombre del perfil en la línea `calibracion:` de un registro (ver
+// `describe`); None si se registró sin calibrar
+pub fn profile_name(description: &str) -> Option<&str> {
+    let name = description.split(" | ").next().unwrap_or_default().trim();
+    (!name.is_empty() && name != UNCALIBRATED).then_some(name)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CalibrationPoint {
+    #[serde(rename = "masa")]
+    pub mass: f64, // masa de referencia en kg
+    #[serde(rename = "crudo")]
+    pub raw: f64, // lectura cruda promedio
+}
+
+impl CalibrationPoint {
+    pub fn force(&self) -> f64 {
+        self.mass * G0
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CalibrationProfile {
+    #[serde(rename = "nombre")]
+    pub name: String,
+    #[serde(rename = "centro")]
+    pub center: f64,
+    #[serde(rename = "escala")]
+    pub scale: f64,
+    #[serde(rename = "coeficientes")]
+    pub coefficients: Vec<f64>, // c0 + c1·u + c2·u² + ...
+    #[serde(rename = "puntos", default)]
+    pub points: Vec<CalibrationPoint>,
+}
+
+impl CalibrationProfile {
+    // Ajusta un polinomio del grado pedido a los puntos de referencia
+    pub fn fit(name: &str, points: &[CalibrationPoint], degree: usize) -> Result<Self, String> {
+        if degree == 0 || degree > MAX_DEGREE {
+            return Err(format!("El grado debe estar entre 1 y {}", MAX_DEGREE));
+        }
+        if points.len() <= degree {
+            return Err(format!(
+                "Se necesitan al menos {} puntos para un ajuste de grado {}",
+                degree + 1,
+                degree
+            ));
+        }
+
+        let n = points.len() as f64;
+        let center = points.iter().map(|p| p.raw).sum::<f64>() / n;
+        let scale = points
+            .iter()
+            .map(|p| (p.raw - center).abs())
+            .fold(0.0, f64::max);
+        if scale == 0.0 {
+            return Err("Todas las lecturas crudas son iguales".to_string());
+        }
+
+        // Ecuaciones normales (AᵀA) c = Aᵀy
+        let size = degree + 1;
+        let mut matrix = vec![vec![0.0; size + 1]; size];
+        for point in points {
+            let u = (point.raw - center) / scale;
+            let powers: Vec<f64> = (0..size).map(|k| u.powi(k as i32)).collect();
+            for row in 0..size {
+                for col in 0..size {
+                    matrix[row][col] += powers[row] * powers[col];
+                }
+                matrix[row][size] += powers[row] * point.force();
+            }
+        }
+
+        let coefficients = solve(matrix).ok_or("Los puntos no permiten un ajuste único")?;
+
+        Ok(Self {
+            name: name.to_string(),
+            center,
+            scale,
+            coefficients,
+            points: points.to_vec(),
+        })
+    }
+
+    pub fn apply(&self, raw: f64) -> f64 {
+        let u = (raw - self.center) / self.scale;
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |acc, c| acc * u + c)
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    // Diferencia entre la fuerza ajustada y la de referencia para cada punto
+    pub fn residuals(&self) -> Vec<f64> {
+        self.points
+            .iter()
+            .map(|p| self.apply(p.raw) - p.force())
+            .collect()
+    }
+
+    pub fn rms_residual(&self) -> f64 {
+        let residuals = self.residuals();
+        if residuals.is_empty() {
+            return 0.0;
+        }
+        (residuals.iter().map(|r| r * r).sum::<f64>() / residuals.len() as f64).sqrt()
+    }
+
+    // Descripción de una línea para el registro de la sesión
+    pub fn describe(&self) -> String {
+        let coefficients: Vec<String> = self
+            .coefficients
+            .iter()
+            .map(|c| format!("{:.9e}", c))
+            .collect();
+        format!(
+            "{} | grado {} | centro {} | escala {} | coeficientes [{}]",
+            self.name,
+            self.degree(),
+            self.center,
+            self.scale,
+            coefficients.join(", ")
+        )
+    }
+}
+
+// Eliminación gaussiana con pivoteo parcial sobre una matriz aumentada
+fn solve(mut matrix: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+    let size = matrix.len();
+    for col in 0..size {
+        let pivot = (col..size).max_by(|&a, &b| {
+            matrix[a][col]
+                .abs()
+                .partial_cmp(&matrix[b][col].abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })?;
+        if matrix[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+
+        let (upper, lower) = matrix.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for row in lower.iter_mut() {
+            let factor = row[col] / pivot_row[col];
+            for (value, pivot_value) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+
+    let mut solution = vec![0.0; size];
+    for row in (0..size).rev() {
+        let sum: f64 = (row + 1..size).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (matrix[row][size] - sum) / matrix[row][row];
+    }
+    Some(solution)
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct CalibrationStore {
+    #[serde(rename = "perfil", default)]
+    pub profiles: Vec<CalibrationProfile>,
+}
+
+impl CalibrationStore {
+    // Si el archivo todavía no existe no hay perfiles guardados
+    pub fn load(path: &str) -> Result<Self, String> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Error al leer las calibraciones: {}", e))?;
+        toml::from_str(&text).map_err(|e| format!("Archivo de calibraciones inválido: {}", e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| format!("Error al serializar las calibraciones: {}", e))?;
+        std::fs::write(path, text).map_err(|e| format!("Error al guardar las calibraciones: {}", e))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CalibrationProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    // Reemplaza el perfil si ya existe uno con el mismo nombre
+    pub fn insert(&mut self, profile: CalibrationProfile) {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_name_from_log_line() {
+        let profile = CalibrationProfile::fit(
+            "celda-50kg",
+            &[
+                CalibrationPoint {
+                    mass: 0.0,
+                    raw: 100.0,
+                },
+                CalibrationPoint {
+                    mass: 1.0,
+                    raw: 1100.0,
+                },
+            ],
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(profile_name(&profile.describe()), Some("celda-50kg"));
+        assert_eq!(profile_name(UNCALIBRATED), None);
+        assert_eq!(profile_name(""), None);
+    }
+}
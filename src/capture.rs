@@ -0,0 +1,330 @@
+// Captura cruda del enlace.
+//
+// Junto a cada registro CSV se guarda todo lo que llegó de la fuente, byte
+// por byte y con la hora de recepción del host, incluso lo que el
+// decodificador descartó. Así queda la evidencia de lo que envió la placa y
+// el ensayo se puede reprocesar con un decodificador corregido, otro
+// esquema u otra calibración (`apogeo reprocess`).
+//
+// Formato (enteros y flotantes little-endian):
+//
+//   "APGCAP01"                               una vez, al comienzo del archivo
+//   'S' inicio:i64 formato:u8                inicio de sesión: µs desde 1970
+//                                            (UTC) y 0 ASCII / 1 binario
+//   'M' largo:u32 texto[largo]               cabecera del registro (UTF-8),
+//                                            una línea `clave: valor` por
+//                                            renglón, sin el `#`
+//   'D' tiempo:u64 largo:u16 bytes[largo]    una lectura de la fuente
+//   'T' tiempo:u64 ventana:f64               tara pedida, con su ventana en s
+//
+// Los tiempos son µs desde el inicio de la sesión, en la misma escala que
+// la columna Tiempo del registro. Como el CSV, el archivo se continúa si ya
+// existe, con una sesión por cada Iniciar. La cabecera (operador, notas,
+// masa de propelente...) va justo después de su 'S' y al reprocesar se
+// conserva; las capturas anteriores a ella no la tienen.
+
+use crate::framing::WireFormat;
+use chrono::{DateTime, TimeZone, Utc};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+const MAGIC: &[u8; 8] = b"APGCAP01";
+
+const SESSION_TAG: u8 = b'S';
+const HEADER_TAG: u8 = b'M';
+const DATA_TAG: u8 = b'D';
+const TARE_TAG: u8 = b'T';
+
+// Captura que acompaña a un registro: mismo nombre, extensión .cap
+pub fn capture_path(log_path: &str) -> String {
+    Path::new(log_path)
+        .with_extension("cap")
+        .to_string_lossy()
+        .to_string()
+}
+
+pub enum Record {
+    Data { time: Duration, bytes: Vec<u8> },
+    Tare { time: Duration, window: f64 },
+}
+
+impl Record {
+    pub fn time(&self) -> Duration {
+        match self {
+            Record::Data { time, .. } | Record::Tare { time, .. } => *time,
+        }
+    }
+}
+
+pub struct CaptureSession {
+    pub started_at: DateTime<Utc>,
+    pub wire_format: WireFormat,
+    pub header: Vec<String>, // líneas `clave: valor` del registro original
+    pub records: Vec<Record>,
+}
+
+impl CaptureSession {
+    pub fn bytes(&self) -> usize {
+        self.records
+            .iter()
+            .map(|record| match record {
+                Record::Data { bytes, .. } => bytes.len(),
+                Record::Tare { .. } => 0,
+            })
+            .sum()
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.records.last().map_or(Duration::ZERO, Record::time)
+    }
+}
+
+pub struct CaptureWriter {
+    file: File,
+}
+
+impl CaptureWriter {
+    // Abre (o continúa) la captura y anota el inicio de la sesión con la
+    // cabecera del registro
+    pub fn create(
+        path: &str,
+        started_at: DateTime<Utc>,
+        wire_format: WireFormat,
+        header_lines: &[String],
+    ) -> Result<Self, String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Error al abrir la captura: {}", e))?;
+        let empty = file
+            .metadata()
+            .map_err(|e| format!("Error al abrir la captura: {}", e))?
+            .len()
+            == 0;
+
+        let mut header = Vec::new();
+        if empty {
+            header.extend_from_slice(MAGIC);
+        }
+        header.push(SESSION_TAG);
+        header.extend_from_slice(&started_at.timestamp_micros().to_le_bytes());
+        header.push(match wire_format {
+            WireFormat::Ascii => 0,
+            WireFormat::Binary => 1,
+        });
+        let text = header_lines.join("\n");
+        header.push(HEADER_TAG);
+        header.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        header.extend_from_slice(text.as_bytes());
+        file.write_all(&header)
+            .map_err(|e| format!("Error al escribir la captura: {}", e))?;
+        Ok(Self { file })
+    }
+
+    // Cada registro va en una sola escritura, sin buffer propio, para que
+    // un corte del programa no pierda lo ya recibido
+    pub fn data(&mut self, time: Duration, bytes: &[u8]) -> Result<(), String> {
+        let mut record = Vec::with_capacity(11 + bytes.len());
+        for chunk in bytes.chunks(u16::MAX as usize) {
+            record.push(DATA_TAG);
+            record.extend_from_slice(&micros(time).to_le_bytes());
+            record.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+            record.extend_from_slice(chunk);
+        }
+        self.write(&record)
+    }
+
+    pub fn tare(&mut self, time: Duration, window: f64) -> Result<(), String> {
+        let mut record = vec![TARE_TAG];
+        record.extend_from_slice(&micros(time).to_le_bytes());
+        record.extend_from_slice(&window.to_le_bytes());
+        self.write(&record)
+    }
+
+    pub fn sync(&self) {
+        let _ = self.file.sync_all();
+    }
+
+    fn write(&mut self, record: &[u8]) -> Result<(), String> {
+        self.file
+            .write_all(record)
+            .map_err(|e| format!("Error al escribir la captura: {}", e))
+    }
+}
+
+fn micros(time: Duration) -> u64 {
+    time.as_micros().min(u64::MAX as u128) as u64
+}
+
+// Lee todas las sesiones de una captura. Un registro cortado al final (el
+// programa terminó a mitad de una escritura) se ignora
+pub fn read(path: &str) -> Result<Vec<CaptureSession>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Error al abrir la captura: {}", e))?;
+    if !bytes.starts_with(MAGIC) {
+        return Err(format!("{} no es una captura de Apogeo", path));
+    }
+
+    let mut reader = Reader {
+        bytes: &bytes,
+        pos: MAGIC.len(),
+    };
+    let mut sessions: Vec<CaptureSession> = Vec::new();
+    while let Some(&tag) = reader.bytes.get(reader.pos) {
+        let start = reader.pos;
+        reader.pos += 1;
+        let parsed = match tag {
+            SESSION_TAG => reader.session().map(|session| sessions.push(session)),
+            HEADER_TAG => {
+                let session = sessions
+                    .last_mut()
+                    .ok_or(format!("Captura dañada en el byte {}", start))?;
+                reader.header().map(|header| session.header = header)
+            }
+            DATA_TAG | TARE_TAG => {
+                let session = sessions
+                    .last_mut()
+                    .ok_or(format!("Captura dañada en el byte {}", start))?;
+                let record = if tag == DATA_TAG {
+                    reader.data()
+                } else {
+                    reader.tare()
+                };
+                record.map(|record| session.records.push(record))
+            }
+            _ => return Err(format!("Captura dañada en el byte {}", start)),
+        };
+        if parsed.is_none() {
+            break;
+        }
+    }
+
+    if sessions.is_empty() {
+        return Err(format!("{} no tiene sesiones", path));
+    }
+    Ok(sessions)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let chunk = self.bytes.get(self.pos..self.pos + N)?;
+        self.pos += N;
+        chunk.try_into().ok()
+    }
+
+    fn time(&mut self) -> Option<Duration> {
+        self.take()
+            .map(|b| Duration::from_micros(u64::from_le_bytes(b)))
+    }
+
+    fn session(&mut self) -> Option<CaptureSession> {
+        let micros = i64::from_le_bytes(self.take()?);
+        let [format] = self.take()?;
+        Some(CaptureSession {
+            started_at: Utc.timestamp_micros(micros).single()?,
+            wire_format: if format == 1 {
+                WireFormat::Binary
+            } else {
+                WireFormat::Ascii
+            },
+            header: Vec::new(),
+            records: Vec::new(),
+        })
+    }
+
+    fn header(&mut self) -> Option<Vec<String>> {
+        let len = u32::from_le_bytes(self.take()?) as usize;
+        let text = self.bytes.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(
+            String::from_utf8_lossy(text)
+                .lines()
+                .map(|line| line.to_string())
+                .collect(),
+        )
+    }
+
+    fn data(&mut self) -> Option<Record> {
+        let time = self.time()?;
+        let len = u16::from_le_bytes(self.take()?) as usize;
+        let bytes = self.bytes.get(self.pos..self.pos + len)?.to_vec();
+        self.pos += len;
+        Some(Record::Data { time, bytes })
+    }
+
+    fn tare(&mut self) -> Option<Record> {
+        let time = self.time()?;
+        let window = f64::from_le_bytes(self.take()?);
+        Some(Record::Tare { time, window })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_read_round_trip() {
+        let path = std::env::temp_dir().join(format!("apogeo_{}_captura.cap", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let first = Utc.timestamp_micros(1_773_500_966_535_000).unwrap();
+        let header = vec![
+            "operador: Ana".to_string(),
+            "masa_propelente: 0.1 kg".to_string(),
+        ];
+
+        let mut writer = CaptureWriter::create(path, first, WireFormat::Ascii, &header).unwrap();
+        writer
+            .data(Duration::from_millis(10), b"00:00:00:010,1.5")
+            .unwrap();
+        writer.tare(Duration::from_millis(20), 0.5).unwrap();
+        drop(writer);
+        // Una segunda sesión continúa el archivo; un registro cortado al
+        // final se ignora
+        let mut writer = CaptureWriter::create(path, first, WireFormat::Binary, &[]).unwrap();
+        writer
+            .data(Duration::from_millis(5), &[0xAA, 0x55])
+            .unwrap();
+        drop(writer);
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(&[DATA_TAG, 1, 2]).unwrap();
+
+        let sessions = read(path).unwrap();
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].started_at, first);
+        assert!(sessions[0].wire_format == WireFormat::Ascii);
+        assert_eq!(sessions[0].header, header);
+        assert_eq!(sessions[0].bytes(), 16);
+        assert_eq!(sessions[0].duration(), Duration::from_millis(20));
+        match &sessions[0].records[..] {
+            [Record::Data { bytes, .. }, Record::Tare { window, .. }] => {
+                assert_eq!(bytes, b"00:00:00:010,1.5");
+                assert_eq!(*window, 0.5);
+            }
+            _ => panic!("registros inesperados"),
+        }
+        assert!(sessions[1].wire_format == WireFormat::Binary);
+        assert!(sessions[1].header.is_empty());
+        assert_eq!(sessions[1].records.len(), 1);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let path = std::env::temp_dir().join(format!("apogeo_{}_otro.cap", std::process::id()));
+        std::fs::write(&path, b"Tiempo,Empuje\n").unwrap();
+        let result = read(path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+
+        assert!(result.is_err());
+    }
+}
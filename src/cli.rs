@@ -0,0 +1,822 @@
+// Modo de línea de comandos, sin ventana:
+//
+//   apogeo analyze datos.csv [otro.csv ...] [--format text|json]
+//   apogeo record --port /dev/ttyUSB0 [--baud 115200] [--out ensayo.csv]
+//   apogeo record --tcp 192.168.4.1:3333 --out ensayo.csv
+//   apogeo simulate [--csv ensayo.csv | --synthetic] [--speed 1]
+//   apogeo reprocess ensayo.cap [--out nuevo.csv] [--calibration perfil]
+//
+// `analyze` lee los archivos igual que el visor de CSV y calcula las mismas
+// métricas del panel de estadísticas; `record` adquiere igual que el
+// monitoreo en vivo; `simulate` hace de banco sobre un pseudo-terminal;
+// `reprocess` vuelve a decodificar la captura cruda de un ensayo.
+// Sin argumentos se abre la interfaz gráfica.
+
+use apogeo::acquisition::{self, AcquisitionConfig, SharedState};
+use apogeo::analysis::BurnThresholds;
+use apogeo::calibration::{self, CalibrationStore};
+use apogeo::capture;
+use apogeo::dataset::{self, format_timestamp};
+use apogeo::framing::WireFormat;
+use apogeo::report::Report;
+use apogeo::schema::Schema;
+use apogeo::simulator::{self, SimulatorConfig, SyntheticCurve};
+use apogeo::source::{SourceConfig, SourceKind};
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Uso:
+  apogeo                         abre la interfaz gráfica
+  apogeo analyze ARCHIVO... [opciones]
+  apogeo record (--port PUERTO | --tcp HOST:PUERTO | --tcp-listen DIRECCION |
+                --udp DIRECCION | --replay ARCHIVO | --synthetic) [opciones]
+  apogeo simulate [opciones]
+  apogeo reprocess CAPTURA [opciones]
+
+analyze: analiza registros CSV o motores .rse sin abrir la ventana.
+record: registra un ensayo desde la fuente elegida; Ctrl+C lo detiene.
+simulate: transmite un ensayo por un pseudo-terminal como si fuera la placa.
+reprocess: regenera el registro de un ensayo a partir de su captura cruda
+  (el archivo .cap junto al CSV).
+
+Opciones de analyze:
+  --format text|json   formato de salida (por defecto text)
+  --schema ARCHIVO     esquema de canales (por defecto esquema.toml)
+  --mass KG            masa de propelente si el archivo no la trae
+  --delay RETARDO      retardo para la designación del motor (p. ej. 7)
+  --thresholds I,A,C   umbrales de ignición, apagado y acción en % del pico
+                       (por defecto 5,5,10)
+
+Opciones de record:
+  --port PUERTO        puerto serie, p. ej. /dev/ttyUSB0 o COM9
+  --baud BAUDIOS       velocidad del puerto (por defecto 115200)
+  --tcp HOST:PUERTO    conecta a un controlador por TCP (reintenta si se corta)
+  --tcp-listen DIR     espera que el controlador se conecte, p. ej. 0.0.0.0:3333
+  --udp DIRECCION      escucha paquetes UDP, p. ej. 0.0.0.0:3333
+  --replay ARCHIVO     reproduce un registro con sus tiempos
+  --synthetic          genera una curva de empuje sintética
+  --speed X            velocidad de --replay y --synthetic (por defecto 1)
+  --loop               repite --replay o --synthetic al terminar
+  --out ARCHIVO        CSV de salida (por defecto datos.csv)
+  --wire ascii|binary  formato del cable (por defecto ascii)
+  --schema ARCHIVO     esquema de canales (por defecto esquema.toml)
+  --calibration NOMBRE perfil de calibraciones.toml para el empuje
+  --mass KG            masa de propelente que se anota en el registro
+  --operator NOMBRE    operador, para la cabecera del registro
+  --notes TEXTO        notas del ensayo, para la cabecera del registro
+
+Opciones de simulate:
+  --csv ARCHIVO        registro CSV (o motor .rse) que se reproduce
+  --synthetic          curva sintética (por defecto si no se da --csv)
+  --peak N             empuje máximo de la curva sintética (por defecto 150)
+  --burn S             tiempo de combustión sintético (por defecto 2)
+  --rate HZ            muestras por segundo sintéticas (por defecto 100)
+  --speed X            velocidad de reproducción (por defecto 1, tiempo real)
+  --loop               vuelve a empezar al terminar
+  --wire ascii|binary  formato del cable (por defecto ascii)
+  --schema ARCHIVO     esquema de canales (por defecto esquema.toml)
+
+Opciones de reprocess:
+  --out ARCHIVO        CSV de salida (por defecto CAPTURA_reprocesado.csv)
+  --session N          sesión de la captura, desde 1 (por defecto la última)
+  --wire ascii|binary  formato del cable (por defecto el de la captura)
+  --schema ARCHIVO     esquema de canales (por defecto esquema.toml)
+  --calibration NOMBRE perfil de calibraciones.toml (por defecto el del ensayo)
+  --mass KG            masa de propelente (por defecto la de la captura)
+  --notes TEXTO        notas (por defecto las de la captura)
+
+  -h, --help           muestra esta ayuda";
+
+#[derive(PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+struct AnalyzeOptions {
+    files: Vec<String>,
+    format: Format,
+    schema_path: String,
+    propellant_mass: Option<f64>,
+    delay: String,
+    thresholds: BurnThresholds,
+}
+
+// Devuelve el código de salida, o None si hay que abrir la interfaz gráfica
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first()?;
+    let code = match command.as_str() {
+        "analyze" => match parse_analyze(&args[1..]) {
+            Ok(Some(options)) => analyze(&options),
+            Ok(None) => {
+                println!("{}", USAGE);
+                0
+            }
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, USAGE);
+                2
+            }
+        },
+        "record" => match parse_record(&args[1..]) {
+            Ok(Some(options)) => record(&options),
+            Ok(None) => {
+                println!("{}", USAGE);
+                0
+            }
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, USAGE);
+                2
+            }
+        },
+        "simulate" => match parse_simulate(&args[1..]) {
+            Ok(Some(options)) => simulate(&options),
+            Ok(None) => {
+                println!("{}", USAGE);
+                0
+            }
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, USAGE);
+                2
+            }
+        },
+        "reprocess" => match parse_reprocess(&args[1..]) {
+            Ok(Some(options)) => reprocess(options),
+            Ok(None) => {
+                println!("{}", USAGE);
+                0
+            }
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, USAGE);
+                2
+            }
+        },
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            0
+        }
+        other => {
+            eprintln!("Comando desconocido: {}\n\n{}", other, USAGE);
+            2
+        }
+    };
+    Some(code)
+}
+
+// None si se pidió la ayuda
+fn parse_analyze(args: &[String]) -> Result<Option<AnalyzeOptions>, String> {
+    let mut options = AnalyzeOptions {
+        files: Vec::new(),
+        format: Format::Text,
+        schema_path: "esquema.toml".to_string(),
+        propellant_mass: None,
+        delay: String::new(),
+        thresholds: BurnThresholds::default(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Falta el valor de {}", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    other => return Err(format!("Formato desconocido: {}", other)),
+                }
+            }
+            "--schema" => options.schema_path = value()?.clone(),
+            "--mass" => {
+                let mass = value()?;
+                options.propellant_mass = Some(
+                    mass.parse()
+                        .map_err(|_| format!("Masa de propelente inválida: {}", mass))?,
+                );
+            }
+            "--delay" => options.delay = value()?.clone(),
+            "--thresholds" => {
+                let text = value()?;
+                let values: Vec<f64> = text
+                    .split(',')
+                    .map(|v| v.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("Umbrales inválidos: {}", text))?;
+                if values.len() != 3 || values.iter().any(|v| *v <= 0.0 || *v >= 100.0) {
+                    return Err(format!("Umbrales inválidos: {}", text));
+                }
+                options.thresholds = BurnThresholds {
+                    ignition: values[0],
+                    burnout: values[1],
+                    action: values[2],
+                };
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("Opción desconocida: {}", flag));
+            }
+            file => options.files.push(file.to_string()),
+        }
+    }
+
+    if options.files.is_empty() {
+        return Err("Indique al menos un archivo".to_string());
+    }
+    Ok(Some(options))
+}
+
+fn analyze(options: &AnalyzeOptions) -> i32 {
+    let schema = match Schema::load(&options.schema_path) {
+        Ok(schema) => schema,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    // Un archivo que falla no detiene el resto del lote
+    let mut code = 0;
+    let mut reports = Vec::new();
+    for file in &options.files {
+        match analyze_file(&schema, file, options) {
+            Ok(report) => reports.push(report),
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                code = 1;
+            }
+        }
+    }
+
+    match options.format {
+        Format::Text => {
+            let texts: Vec<String> = reports.iter().map(|r| r.to_text()).collect();
+            write_stdout(&texts.join("\n"));
+        }
+        Format::Json => {
+            // Un objeto por archivo; una lista si se pidieron varios
+            let json = if options.files.len() == 1 {
+                reports.first().map(serde_json::to_string_pretty)
+            } else {
+                Some(serde_json::to_string_pretty(&reports))
+            };
+            match json {
+                Some(Ok(json)) => write_stdout(&format!("{}\n", json)),
+                Some(Err(e)) => {
+                    eprintln!("Error al generar el JSON: {}", e);
+                    code = 1;
+                }
+                None => {}
+            }
+        }
+    }
+    code
+}
+
+fn analyze_file(schema: &Schema, file: &str, options: &AnalyzeOptions) -> Result<Report, String> {
+    let dataset = dataset::load(schema, file)?;
+    if dataset.data_points.is_empty() {
+        return Err("El archivo no tiene datos".to_string());
+    }
+    if dataset.sessions > 1 {
+        eprintln!(
+            "{}: el archivo tiene {} sesiones; se analiza la última",
+            file, dataset.sessions
+        );
+    }
+
+    // La masa de la línea de comandos solo se usa si el archivo no la trae,
+    // igual que la masa ingresada en el panel
+    let propellant_mass = dataset
+        .propellant_mass
+        .or(options.propellant_mass)
+        .unwrap_or(0.0);
+    let delay = match &dataset.engine {
+        Some(header) if options.delay.is_empty() => header.delays.as_str(),
+        _ => options.delay.as_str(),
+    };
+
+    Ok(Report::new(
+        file,
+        schema,
+        &dataset.data_points,
+        options.thresholds,
+        propellant_mass,
+        delay,
+    ))
+}
+
+// Una tubería cerrada (p. ej. `| head`) no es un error del análisis
+fn write_stdout(text: &str) {
+    let _ = std::io::stdout().lock().write_all(text.as_bytes());
+}
+
+struct RecordOptions {
+    source: SourceConfig,
+    file_path: String,
+    wire_format: WireFormat,
+    schema_path: String,
+    calibration: Option<String>,
+    propellant_mass: f64,
+    operator: String,
+    notes: String,
+}
+
+// None si se pidió la ayuda
+fn parse_record(args: &[String]) -> Result<Option<RecordOptions>, String> {
+    let mut source = None;
+    let mut options = RecordOptions {
+        source: SourceConfig::default(),
+        file_path: "datos.csv".to_string(),
+        wire_format: WireFormat::Ascii,
+        schema_path: "esquema.toml".to_string(),
+        calibration: None,
+        propellant_mass: 0.0,
+        operator: String::new(),
+        notes: String::new(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Falta el valor de {}", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--port" => {
+                options.source.port_name = value()?.clone();
+                source = Some(SourceKind::Serial);
+            }
+            "--baud" => {
+                let baud = value()?;
+                options.source.baud_rate = baud
+                    .parse()
+                    .map_err(|_| format!("Velocidad inválida: {}", baud))?;
+            }
+            "--tcp" => {
+                options.source.tcp_address = value()?.clone();
+                source = Some(SourceKind::Tcp);
+            }
+            "--tcp-listen" => {
+                options.source.tcp_listen = value()?.clone();
+                source = Some(SourceKind::TcpServer);
+            }
+            "--udp" => {
+                options.source.udp_address = value()?.clone();
+                source = Some(SourceKind::Udp);
+            }
+            "--replay" => {
+                options.source.replay_path = value()?.clone();
+                source = Some(SourceKind::Replay);
+            }
+            "--synthetic" => source = Some(SourceKind::Synthetic),
+            "--speed" => options.source.speed = parse_number(value()?, "Velocidad")?,
+            "--loop" => options.source.repeat = true,
+            "--out" => options.file_path = value()?.clone(),
+            "--wire" => {
+                options.wire_format = match value()?.as_str() {
+                    "ascii" => WireFormat::Ascii,
+                    "binary" => WireFormat::Binary,
+                    other => return Err(format!("Formato de cable desconocido: {}", other)),
+                }
+            }
+            "--schema" => options.schema_path = value()?.clone(),
+            "--calibration" => options.calibration = Some(value()?.clone()),
+            "--mass" => {
+                let mass = value()?;
+                options.propellant_mass = mass
+                    .parse()
+                    .map_err(|_| format!("Masa de propelente inválida: {}", mass))?;
+            }
+            "--operator" => options.operator = value()?.clone(),
+            "--notes" => options.notes = value()?.clone(),
+            other => return Err(format!("Opción desconocida: {}", other)),
+        }
+    }
+
+    options.source.kind = source.ok_or(
+        "Indique la fuente con --port, --tcp, --tcp-listen, --udp, --replay o --synthetic",
+    )?;
+    Ok(Some(options))
+}
+
+fn record(options: &RecordOptions) -> i32 {
+    let schema = match Schema::load(&options.schema_path) {
+        Ok(schema) => schema,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let calibration = match &options.calibration {
+        Some(name) => match CalibrationStore::load("calibraciones.toml") {
+            Ok(store) => match store.get(name) {
+                Some(profile) => Some(profile.clone()),
+                None => {
+                    eprintln!("No existe el perfil de calibración \"{}\"", name);
+                    return 1;
+                }
+            },
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        },
+        None => None,
+    };
+
+    let config = AcquisitionConfig {
+        source: options.source.clone(),
+        wire_format: options.wire_format,
+        schema,
+        file_path: options.file_path.clone(),
+        calibration,
+        propellant_mass: options.propellant_mass,
+        tare_seconds: 0.0,
+        start_time: Instant::now(),
+        operator: options.operator.clone(),
+        notes: options.notes.clone(),
+    };
+    let shared = SharedState::default();
+
+    // Ctrl+C solo pide detener el hilo; el archivo se cierra al salir de él
+    let running = shared.running.clone();
+    if let Err(e) = ctrlc::set_handler(move || *running.lock().unwrap() = false) {
+        eprintln!("Error al instalar el manejador de Ctrl+C: {}", e);
+        return 1;
+    }
+
+    eprintln!(
+        "Registrando desde {} en {} (Ctrl+C para terminar)",
+        options.source.describe(),
+        options.file_path
+    );
+    let start_time = config.start_time;
+    let worker = shared.clone();
+    let thread = thread::spawn(move || acquisition::run(&config, &worker));
+    while !thread.is_finished() {
+        print_status(&shared, start_time);
+        thread::sleep(Duration::from_millis(250));
+    }
+    let result = thread
+        .join()
+        .unwrap_or(Err("El hilo de adquisición falló".to_string()));
+
+    eprintln!();
+    match result {
+        Ok(()) => {
+            let stats = shared.frame_stats.lock().unwrap();
+            eprintln!(
+                "Registro terminado: {} registros en {}",
+                stats.frames, options.file_path
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+// Una sola línea que se reescribe: tiempo, enlace, registros, errores y
+// último dato
+fn print_status(shared: &SharedState, start_time: Instant) {
+    let stats = shared.frame_stats.lock().unwrap().clone();
+    let errors = stats.malformed + stats.truncated + stats.oversize + stats.crc_errors;
+    let last_data = shared.last_data.lock().unwrap().clone();
+    let link = shared.source_status.lock().unwrap().state.label();
+    let mut status = format!(
+        "\r{} | {} | {} registros | {} errores | {} perdidos | {}",
+        format_timestamp(start_time.elapsed()),
+        link,
+        stats.frames,
+        errors,
+        stats.lost,
+        last_data
+    );
+    // Borra el resto de la línea anterior
+    status.push_str("\x1b[K");
+    let mut stderr = std::io::stderr().lock();
+    let _ = stderr.write_all(status.as_bytes());
+    let _ = stderr.flush();
+}
+
+// Reprocesar es registrar con la captura como fuente, sin pausas; el
+// formato del cable, si no se indica, se toma de la captura
+struct ReprocessOptions {
+    record: RecordOptions,
+    wire_format: Option<WireFormat>,
+}
+
+// None si se pidió la ayuda
+fn parse_reprocess(args: &[String]) -> Result<Option<ReprocessOptions>, String> {
+    let mut capture_path = None;
+    let mut file_path = None;
+    let mut options = ReprocessOptions {
+        record: RecordOptions {
+            source: SourceConfig {
+                kind: SourceKind::Capture,
+                speed: f64::INFINITY,
+                ..SourceConfig::default()
+            },
+            file_path: String::new(),
+            wire_format: WireFormat::Ascii,
+            schema_path: "esquema.toml".to_string(),
+            calibration: None,
+            propellant_mass: 0.0,
+            operator: String::new(),
+            notes: String::new(),
+        },
+        wire_format: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Falta el valor de {}", arg))
+        };
+        let record = &mut options.record;
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--out" => file_path = Some(value()?.clone()),
+            "--session" => {
+                let session = value()?;
+                record.source.capture_session = session
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("Sesión inválida: {}", session))?;
+            }
+            "--wire" => {
+                options.wire_format = Some(match value()?.as_str() {
+                    "ascii" => WireFormat::Ascii,
+                    "binary" => WireFormat::Binary,
+                    other => return Err(format!("Formato de cable desconocido: {}", other)),
+                })
+            }
+            "--schema" => record.schema_path = value()?.clone(),
+            "--calibration" => record.calibration = Some(value()?.clone()),
+            "--mass" => {
+                let mass = value()?;
+                record.propellant_mass = mass
+                    .parse()
+                    .map_err(|_| format!("Masa de propelente inválida: {}", mass))?;
+            }
+            "--notes" => record.notes = value()?.clone(),
+            flag if flag.starts_with("--") => return Err(format!("Opción desconocida: {}", flag)),
+            path if capture_path.is_none() => capture_path = Some(path.to_string()),
+            other => return Err(format!("Sobra el argumento: {}", other)),
+        }
+    }
+
+    let capture_path = capture_path.ok_or("Indique la captura a reprocesar")?;
+    options.record.file_path = file_path.unwrap_or_else(|| {
+        std::path::Path::new(&capture_path)
+            .with_extension("")
+            .to_string_lossy()
+            .to_string()
+            + "_reprocesado.csv"
+    });
+    options.record.source.capture_path = capture_path;
+    Ok(Some(options))
+}
+
+fn reprocess(mut options: ReprocessOptions) -> i32 {
+    let record_options = &mut options.record;
+    let sessions = match capture::read(&record_options.source.capture_path) {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let index = match record_options.source.capture_session {
+        0 => sessions.len() - 1,
+        n => n - 1,
+    };
+    let session = match sessions.get(index) {
+        Some(session) => session,
+        None => {
+            eprintln!(
+                "La captura tiene {} sesiones, no existe la {}",
+                sessions.len(),
+                index + 1
+            );
+            return 1;
+        }
+    };
+    eprintln!(
+        "Captura con {} sesión(es); sesión {}: inicio {}, {} bytes en {:.1} s",
+        sessions.len(),
+        index + 1,
+        dataset::format_utc(session.started_at),
+        session.bytes(),
+        session.duration().as_secs_f64()
+    );
+    record_options.wire_format = options.wire_format.unwrap_or(session.wire_format);
+
+    // Sin --calibration se usa el perfil con el que se registró el ensayo;
+    // reprocesar en cuentas crudas un ensayo calibrado sería un error
+    if record_options.calibration.is_none() {
+        let recorded = dataset::read_header(&session.header).0.calibration;
+        match recorded.as_deref() {
+            Some(description) => {
+                if let Some(name) = calibration::profile_name(description) {
+                    let store = match CalibrationStore::load("calibraciones.toml") {
+                        Ok(store) => store,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return 1;
+                        }
+                    };
+                    match store.get(name) {
+                        Some(profile) if profile.describe() != description => eprintln!(
+                            "Aviso: el perfil \"{}\" cambió desde el ensayo; se usa el actual",
+                            name
+                        ),
+                        Some(_) => {}
+                        None => {
+                            eprintln!(
+                                "El ensayo se registró con la calibración \"{}\", que no está en \
+                                 calibraciones.toml; indique otra con --calibration",
+                                name
+                            );
+                            return 1;
+                        }
+                    }
+                    eprintln!("Calibración del ensayo: {}", name);
+                    record_options.calibration = Some(name.to_string());
+                }
+            }
+            None => eprintln!(
+                "Aviso: la captura no indica la calibración del ensayo; el empuje queda sin \
+                 calibrar salvo que se indique --calibration"
+            ),
+        }
+    }
+    record(record_options)
+}
+
+struct SimulateOptions {
+    csv_path: Option<String>,
+    synthetic: SyntheticCurve,
+    speed: f64,
+    repeat: bool,
+    wire_format: WireFormat,
+    schema_path: String,
+}
+
+fn parse_number(text: &str, what: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(value),
+        _ => Err(format!("{} inválido: {}", what, text)),
+    }
+}
+
+// None si se pidió la ayuda
+fn parse_simulate(args: &[String]) -> Result<Option<SimulateOptions>, String> {
+    let mut options = SimulateOptions {
+        csv_path: None,
+        synthetic: SyntheticCurve::default(),
+        speed: 1.0,
+        repeat: false,
+        wire_format: WireFormat::Ascii,
+        schema_path: "esquema.toml".to_string(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Falta el valor de {}", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--csv" => options.csv_path = Some(value()?.clone()),
+            "--synthetic" => options.csv_path = None,
+            "--peak" => options.synthetic.peak_thrust = parse_number(value()?, "Empuje")?,
+            "--burn" => options.synthetic.burn_time = parse_number(value()?, "Tiempo")?,
+            "--rate" => options.synthetic.rate = parse_number(value()?, "Frecuencia")?,
+            "--speed" => options.speed = parse_number(value()?, "Velocidad")?,
+            "--loop" => options.repeat = true,
+            "--wire" => {
+                options.wire_format = match value()?.as_str() {
+                    "ascii" => WireFormat::Ascii,
+                    "binary" => WireFormat::Binary,
+                    other => return Err(format!("Formato de cable desconocido: {}", other)),
+                }
+            }
+            "--schema" => options.schema_path = value()?.clone(),
+            other => return Err(format!("Opción desconocida: {}", other)),
+        }
+    }
+    Ok(Some(options))
+}
+
+#[cfg(not(unix))]
+fn simulate(_options: &SimulateOptions) -> i32 {
+    eprintln!("El simulador usa pseudo-terminales y solo está disponible en Linux");
+    1
+}
+
+#[cfg(unix)]
+fn simulate(options: &SimulateOptions) -> i32 {
+    use std::sync::{Arc, Mutex};
+
+    let schema = match Schema::load(&options.schema_path) {
+        Ok(schema) => schema,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let points = match &options.csv_path {
+        Some(path) => match dataset::load(&schema, path) {
+            Ok(dataset) => dataset.data_points,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                return 1;
+            }
+        },
+        None => options.synthetic.session(&schema),
+    };
+    let config = SimulatorConfig {
+        points,
+        wire_format: options.wire_format,
+        speed: options.speed,
+        repeat: options.repeat,
+    };
+
+    let mut pty = match simulator::PseudoTerminal::open() {
+        Ok(pty) => pty,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    // El enlace anterior puede haber quedado de un simulador que se cortó
+    let _ = std::fs::remove_file(simulator::SIMULATOR_LINK);
+    let linked = std::os::unix::fs::symlink(&pty.path, simulator::SIMULATOR_LINK).is_ok();
+
+    let running = Arc::new(Mutex::new(true));
+    let handler_running = running.clone();
+    if let Err(e) = ctrlc::set_handler(move || *handler_running.lock().unwrap() = false) {
+        eprintln!("Error al instalar el manejador de Ctrl+C: {}", e);
+        return 1;
+    }
+
+    eprintln!(
+        "Simulador en {}{} ({}, velocidad x{}); Ctrl+C para terminar",
+        pty.path,
+        if linked {
+            format!(" y {}", simulator::SIMULATOR_LINK)
+        } else {
+            String::new()
+        },
+        options.wire_format.label(),
+        options.speed
+    );
+
+    let stats = Arc::new(Mutex::new(simulator::SimulatorStats::default()));
+    let result = thread::scope(|scope| {
+        let worker = scope.spawn(|| simulator::run(&mut pty, &schema, &config, &running, &stats));
+        while !worker.is_finished() {
+            let current = *stats.lock().unwrap();
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(
+                stderr,
+                "\r{:.2} s | {} enviados | {} descartados\x1b[K",
+                current.time, current.sent, current.dropped
+            );
+            let _ = stderr.flush();
+            thread::sleep(Duration::from_millis(250));
+        }
+        worker
+            .join()
+            .unwrap_or(Err("El hilo del simulador falló".to_string()))
+    });
+
+    if linked {
+        let _ = std::fs::remove_file(simulator::SIMULATOR_LINK);
+    }
+    eprintln!();
+    match result {
+        Ok(()) => {
+            let stats = stats.lock().unwrap();
+            eprintln!(
+                "Simulación terminada: {} registros enviados, {} descartados",
+                stats.sent, stats.dropped
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
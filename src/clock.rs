@@ -0,0 +1,289 @@
+// Base de tiempo a partir del reloj de la placa.
+//
+// Cuando la placa envía su micros() o millis() en cada registro, el tiempo
+// de la muestra sale de ese contador y no de cuándo la lectura llegó al
+// host, así la latencia del USB y el buffering no se cuelan en el dt de la
+// integración. ClockSync desenrolla las vueltas del contador y lo ancla a
+// la hora del host de la primera muestra, de modo que ambos tiempos quedan
+// en la misma escala (segundos desde Iniciar).
+//
+// Además compara los dos relojes: la deriva es la pendiente del ajuste
+// lineal de host - placa, y la latencia es cuánto más tarde que el
+// registro más rápido de las últimas muestras llega cada uno.
+
+use crate::schema::DeviceClock;
+use std::collections::VecDeque;
+
+// Muestras recientes con las que se estiman latencia y jitter
+const LATENCY_WINDOW: usize = 500;
+
+// Un reinicio de la placa se reconoce en el propio contador: un retroceso
+// de más de STEP_TOLERANCE, o un avance mayor que lo que avanzó el host
+// más STEP_TOLERANCE y STEP_MARGIN de ese hueco. Un contador que avanza
+// menos que el host no es un reinicio sino latencia del enlace (USB, TCP,
+// un cuadro lento de la interfaz), que es justo lo que hay que sacar del dt
+const STEP_TOLERANCE: f64 = 0.25; // s
+const STEP_MARGIN: f64 = 0.1; // fracción del tiempo del host
+
+#[derive(Clone, Copy, Default)]
+pub struct ClockStats {
+    pub drift_ppm: f64,  // positiva si el reloj del host adelanta
+    pub latency_ms: f64, // promedio, respecto del registro más rápido
+    pub jitter_ms: f64,  // desvío estándar de la latencia
+    pub wraps: u64,      // vueltas del contador
+    pub resets: u64,     // saltos que se tomaron como reinicio de la placa
+}
+
+pub struct ClockSync {
+    clock: DeviceClock,
+    last: Option<u64>, // último contador aceptado
+    last_host: f64,    // hora del host (s) de ese registro
+    ticks: u64,        // cuentas desenrolladas desde el ancla
+    anchor: f64,       // hora del host (s) en el ancla
+    wraps: u64,
+    resets: u64,
+    // Sumas del ajuste lineal de host - placa contra tiempo de placa
+    n: f64,
+    sx: f64,
+    sy: f64,
+    sxx: f64,
+    sxy: f64,
+    recent: VecDeque<(f64, f64)>, // (tiempo de placa, host - placa)
+}
+
+impl ClockSync {
+    pub fn new(clock: DeviceClock) -> Self {
+        Self {
+            clock,
+            last: None,
+            last_host: 0.0,
+            ticks: 0,
+            anchor: 0.0,
+            wraps: 0,
+            resets: 0,
+            n: 0.0,
+            sx: 0.0,
+            sy: 0.0,
+            sxx: 0.0,
+            sxy: 0.0,
+            recent: VecDeque::with_capacity(LATENCY_WINDOW),
+        }
+    }
+
+    // Convierte el contador recibido en segundos desde Iniciar; `host` es
+    // la hora del host (s) a la que llegó el registro
+    pub fn update(&mut self, counter: f64, host: f64) -> f64 {
+        let modulus = self.clock.modulus();
+        let tick = self.clock.seconds_per_tick();
+        let raw = (counter.max(0.0) as u64) % modulus;
+
+        match self.last {
+            Some(last) => {
+                // El avance del contador se compara con lo que avanzó el host
+                // desde el registro anterior. De ahí sale también cuántas
+                // vueltas completas dio el contador en un corte largo
+                let elapsed = (host - self.last_host).max(0.0);
+                let forward = (raw + modulus - last) % modulus;
+                let backward = (last + modulus - raw) % modulus;
+                let laps = ((elapsed / tick - forward as f64) / modulus as f64)
+                    .round()
+                    .max(0.0) as u64;
+                let step = forward + laps * modulus;
+
+                // Solo un avance que el host no explica puede ser un retroceso
+                // o un reinicio; quedarse atrás del host es latencia
+                let ahead = step as f64 * tick > elapsed * (1.0 + STEP_MARGIN) + STEP_TOLERANCE;
+                if ahead && backward < forward && backward as f64 * tick <= STEP_TOLERANCE {
+                    // Un retroceso de pocas cuentas es jitter de la placa: la
+                    // muestra queda en el último tiempo aceptado, sin
+                    // retroceder ni volver a anclar
+                    return self.anchor + self.ticks as f64 * tick;
+                } else if ahead {
+                    self.resets += 1;
+                    self.anchor_at(host);
+                } else {
+                    self.wraps += laps + u64::from(raw < last);
+                    self.ticks += step;
+                }
+            }
+            None => self.anchor_at(host),
+        }
+        self.last = Some(raw);
+        self.last_host = host;
+
+        let time = self.anchor + self.ticks as f64 * tick;
+
+        let x = time - self.anchor;
+        let y = host - time;
+        self.n += 1.0;
+        self.sx += x;
+        self.sy += y;
+        self.sxx += x * x;
+        self.sxy += x * y;
+        if self.recent.len() == LATENCY_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((x, y));
+
+        time
+    }
+
+    fn anchor_at(&mut self, host: f64) {
+        self.ticks = 0;
+        self.anchor = host;
+        self.n = 0.0;
+        self.sx = 0.0;
+        self.sy = 0.0;
+        self.sxx = 0.0;
+        self.sxy = 0.0;
+        self.recent.clear();
+    }
+
+    pub fn resets(&self) -> u64 {
+        self.resets
+    }
+
+    pub fn stats(&self) -> ClockStats {
+        let denominator = self.n * self.sxx - self.sx * self.sx;
+        let (slope, intercept) = if self.n >= 2.0 && denominator > 0.0 {
+            let slope = (self.n * self.sxy - self.sx * self.sy) / denominator;
+            (slope, (self.sy - slope * self.sx) / self.n)
+        } else {
+            (0.0, 0.0)
+        };
+
+        // Lo que queda de host - placa después de quitar la deriva es la
+        // latencia más una constante; el registro más rápido marca el cero
+        let residuals: Vec<f64> = self
+            .recent
+            .iter()
+            .map(|&(x, y)| y - (intercept + slope * x))
+            .collect();
+        let (latency_ms, jitter_ms) = if residuals.is_empty() {
+            (0.0, 0.0)
+        } else {
+            let fastest = residuals.iter().copied().fold(f64::INFINITY, f64::min);
+            let count = residuals.len() as f64;
+            let mean = residuals.iter().sum::<f64>() / count;
+            let variance = residuals.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / count;
+            ((mean - fastest) * 1e3, variance.sqrt() * 1e3)
+        };
+
+        ClockStats {
+            drift_ppm: slope * 1e6,
+            latency_ms,
+            jitter_ms,
+            wraps: self.wraps,
+            resets: self.resets,
+        }
+    }
+}
+
+impl ClockStats {
+    pub fn describe(&self) -> String {
+        format!(
+            "deriva {:+.1} ppm | latencia {:.2} ms | jitter {:.2} ms | vueltas {} | reinicios {}",
+            self.drift_ppm, self.latency_ms, self.jitter_ms, self.wraps, self.resets
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ClockUnit;
+
+    fn millis(bits: u32) -> DeviceClock {
+        DeviceClock {
+            unit: ClockUnit::Millis,
+            bits,
+        }
+    }
+
+    // Registros a 100 Hz: (contador en ms, hora del host en s)
+    fn run(sync: &mut ClockSync, samples: &[(u64, f64)]) -> Vec<f64> {
+        samples
+            .iter()
+            .map(|&(counter, host)| sync.update(counter as f64, host))
+            .collect()
+    }
+
+    #[test]
+    fn host_stall_keeps_board_time() {
+        let mut sync = ClockSync::new(millis(32));
+        let mut samples: Vec<(u64, f64)> = (0..50).map(|i| (i * 10, i as f64 * 0.01)).collect();
+        // El host se traba 0.4 s y después llega todo lo acumulado de golpe
+        samples.push((500, 0.895));
+        samples.extend((51..60).map(|i| (i * 10, 0.896)));
+        let times = run(&mut sync, &samples);
+
+        assert!((times[50] - 0.5).abs() < 1e-9);
+        assert!((times[59] - 0.59).abs() < 1e-9);
+        assert_eq!(sync.resets(), 0);
+    }
+
+    #[test]
+    fn counter_wraps() {
+        // 10 bits en ms: da la vuelta cada 1.024 s
+        let clock = millis(10);
+        let mut sync = ClockSync::new(clock);
+        let samples: Vec<(u64, f64)> = (0..300)
+            .map(|i| {
+                let time = i as f64 * 0.01;
+                (clock.counter(time) as u64, time)
+            })
+            .collect();
+        let times = run(&mut sync, &samples);
+
+        assert!((times[299] - 2.99).abs() < 1e-9);
+        assert_eq!(sync.stats().wraps, 2);
+        assert_eq!(sync.resets(), 0);
+    }
+
+    #[test]
+    fn long_gap_counts_missed_laps() {
+        let clock = millis(10);
+        let mut sync = ClockSync::new(clock);
+        let times = run(
+            &mut sync,
+            &[(0, 0.0), (10, 0.01), (clock.counter(5.01) as u64, 5.01)],
+        );
+
+        assert!((times[2] - 5.01).abs() < 1e-9);
+        assert_eq!(sync.resets(), 0);
+    }
+
+    #[test]
+    fn small_backstep_holds_time() {
+        let mut sync = ClockSync::new(millis(32));
+        let times = run(
+            &mut sync,
+            &[(1000, 0.0), (1010, 0.01), (1008, 0.02), (1030, 0.03)],
+        );
+
+        assert_eq!(times[2], times[1]);
+        assert!((times[3] - 0.03).abs() < 1e-9);
+        assert_eq!(sync.resets(), 0);
+    }
+
+    #[test]
+    fn board_restart_reanchors() {
+        let mut sync = ClockSync::new(millis(32));
+        let times = run(
+            &mut sync,
+            &[(60_000, 0.0), (60_010, 0.01), (5, 0.5), (15, 0.51)],
+        );
+
+        assert_eq!(sync.resets(), 1);
+        assert!((times[2] - 0.5).abs() < 1e-9);
+        assert!((times[3] - 0.51).abs() < 1e-9);
+    }
+
+    #[test]
+    fn forward_jump_beyond_host_gap_reanchors() {
+        let mut sync = ClockSync::new(millis(32));
+        run(&mut sync, &[(0, 0.0), (10, 0.01), (30_000, 0.02)]);
+
+        assert_eq!(sync.resets(), 1);
+    }
+}
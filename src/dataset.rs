@@ -0,0 +1,489 @@
+// Datos de un ensayo: lectura de registros CSV y de curvas de motor.
+
+use crate::engine::{self, EngineHeader};
+use crate::schema::{self, Schema};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+// Comentario del registro con la masa de propelente del motor
+pub const PROPELLANT_MASS_TAG: &str = "masa_propelente:";
+
+// Cabecera que el registro escribe al iniciar cada sesión, como
+// comentarios `# clave: valor`. Si un archivo tiene varias sesiones vale
+// la de la última
+#[derive(Clone, Default)]
+pub struct Metadata {
+    pub started_at: Option<DateTime<Utc>>,
+    pub host: Option<String>,
+    pub port: Option<String>,
+    pub baud_rate: Option<u32>,
+    pub version: Option<String>,
+    pub calibration: Option<String>,
+    pub operator: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl Metadata {
+    // Líneas `clave: valor`, sin el `#`
+    pub fn header_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut push = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                // Las notas pueden tener varias líneas
+                lines.push(format!("{}: {}", key, value.replace('\n', "\\n")));
+            }
+        };
+        push("inicio", self.started_at.map(format_utc));
+        push("equipo", self.host.clone());
+        push("puerto", self.port.clone());
+        push("baudios", self.baud_rate.map(|b| b.to_string()));
+        push("version", self.version.clone());
+        push("calibracion", self.calibration.clone());
+        push("operador", self.operator.clone());
+        push("notas", self.notes.clone());
+        lines
+    }
+
+    fn read_comment(&mut self, comment: &str) {
+        let (key, value) = match comment.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => return,
+        };
+        let text = |field: &mut Option<String>| {
+            if field.is_none() {
+                *field = Some(value.replace("\\n", "\n"));
+            }
+        };
+        match key {
+            "inicio" if self.started_at.is_none() => {
+                self.started_at = DateTime::parse_from_rfc3339(value)
+                    .ok()
+                    .map(|t| t.with_timezone(&Utc));
+            }
+            "baudios" if self.baud_rate.is_none() => self.baud_rate = value.parse().ok(),
+            "equipo" => text(&mut self.host),
+            "puerto" => text(&mut self.port),
+            "version" => text(&mut self.version),
+            "calibracion" => text(&mut self.calibration),
+            "operador" => text(&mut self.operator),
+            "notas" => text(&mut self.notes),
+            _ => {}
+        }
+    }
+}
+
+// Metadatos y masa de propelente de una cabecera ya separada en líneas
+// `clave: valor` (la que guarda la captura cruda de cada sesión)
+pub fn read_header(lines: &[String]) -> (Metadata, Option<f64>) {
+    let mut metadata = Metadata::default();
+    let mut propellant_mass = None;
+    for line in lines {
+        match read_mass(line) {
+            Some(mass) => propellant_mass = mass,
+            None => metadata.read_comment(line),
+        }
+    }
+    (metadata, propellant_mass)
+}
+
+// None si el comentario no es la masa; Some(None) si no se entiende el valor
+fn read_mass(comment: &str) -> Option<Option<f64>> {
+    let mass = comment.trim().strip_prefix(PROPELLANT_MASS_TAG)?;
+    Some(mass.trim().trim_end_matches("kg").trim().parse().ok())
+}
+
+#[derive(Clone)]
+pub struct DataPoint {
+    pub time: f64,        // tiempo en segundos
+    pub values: Vec<f64>, // un valor por canal, en el orden del esquema
+}
+
+pub struct Dataset {
+    pub data_points: Vec<DataPoint>,
+    pub propellant_mass: Option<f64>, // kg, si el archivo la trae
+    pub engine: Option<EngineHeader>, // cabecera de un motor .rse
+    pub masses: Vec<[f64; 2]>,        // propelente restante [s, kg] de un .rse
+    pub metadata: Metadata,
+    pub sessions: usize, // sesiones con datos en el archivo; se usa la última
+}
+
+// Carga un registro CSV o un motor RockSim (.rse) según la extensión
+pub fn load(schema: &Schema, path: &str) -> Result<Dataset, String> {
+    if path.to_lowercase().ends_with(".rse") {
+        return load_rse(schema, path);
+    }
+
+    read_csv(schema, path)
+}
+
+// Lee un registro CSV con el esquema dado, junto con la masa de propelente
+// y la cabecera de metadatos si vienen anotadas.
+//
+// Un registro continuado tiene varias sesiones, una por cada Iniciar. Una
+// sesión nueva empieza con su `# inicio:` o su encabezado de columnas, o
+// (en registros sin cabecera) cuando el tiempo vuelve atrás. Solo se
+// devuelve la última, con sus propios metadatos y masa; `sessions` indica
+// cuántas había para poder avisarlo. Una sesión sin datos (se detuvo
+// enseguida) no tapa a la anterior ni le pasa sus metadatos a la siguiente
+pub fn read_csv(schema: &Schema, path: &str) -> Result<Dataset, String> {
+    let file = File::open(path).map_err(|e| format!("Error al abrir el archivo: {}", e))?;
+
+    let reader = BufReader::new(file);
+    let mut data_points: Vec<DataPoint> = Vec::new();
+    let mut propellant_mass = None;
+    let mut metadata = Metadata::default();
+    let mut sessions = 0;
+    let mut previous = None;
+
+    // Sin `# inicio:` ni encabezado, solo el tiempo marca las sesiones
+    let mut delimited = false;
+
+    // Cierra la sesión en curso y empieza otra vacía. Una sesión con datos
+    // queda de respaldo por si las siguientes no tienen; una sin datos se
+    // descarta con sus metadatos. `always` es para `# inicio:`, que siempre
+    // abre una sesión; el encabezado va después de los metadatos de la suya
+    let mut new_session = |data_points: &mut Vec<DataPoint>,
+                           mass: &mut Option<f64>,
+                           metadata: &mut Metadata,
+                           always: bool| {
+        if !data_points.is_empty() {
+            sessions += 1;
+            previous = Some((
+                std::mem::take(data_points),
+                mass.take(),
+                std::mem::take(metadata),
+            ));
+        } else if always {
+            *mass = None;
+            *metadata = Metadata::default();
+        }
+    };
+
+    // Columna del CSV que corresponde a cada canal; sin encabezado se
+    // asume el orden del esquema
+    let mut columns: Vec<Option<usize>> = (1..=schema.channels.len()).map(Some).collect();
+    // Con reloj de la placa (o en un CSV procesado) el tiempo se lee en
+    // segundos de su columna
+    let mut time_column = None;
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| format!("Error al leer línea: {}", e))?;
+
+        // Comentarios del registro (metadatos, eventos, masa)
+        if let Some(comment) = line.strip_prefix('#') {
+            if comment.trim().starts_with("inicio:") {
+                delimited = true;
+                new_session(&mut data_points, &mut propellant_mass, &mut metadata, true);
+            }
+            if let Some(mass) = read_mass(comment) {
+                propellant_mass = mass;
+            } else {
+                metadata.read_comment(comment);
+            }
+            continue;
+        }
+
+        // Cada sesión trae su encabezado, que puede ser de otro esquema
+        let first = line.split(',').next().unwrap_or_default().trim();
+        if schema::TIME_COLUMNS.contains(&first) {
+            delimited = true;
+            new_session(&mut data_points, &mut propellant_mass, &mut metadata, false);
+            let header: Vec<&str> = line.split(',').map(|h| h.trim()).collect();
+            columns = schema
+                .channels
+                .iter()
+                .map(|c| header.iter().position(|h| *h == c.name))
+                .collect();
+            time_column = header
+                .iter()
+                .position(|h| *h == schema::DEVICE_TIME_COLUMN || *h == schema::SECONDS_COLUMN);
+            if columns[schema.thrust_index()].is_none() {
+                return Err(format!(
+                    "El archivo no tiene la columna \"{}\"",
+                    schema.channels[schema.thrust_index()].name
+                ));
+            }
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split(',').collect();
+        let time = match time_column {
+            Some(i) => parts.get(i).and_then(|p| p.trim().parse::<f64>().ok()),
+            None => parse_time_to_seconds(parts[0].trim()),
+        };
+        let time = match time {
+            Some(time) => time,
+            None => continue,
+        };
+        // En un registro viejo, sin cabecera, el tiempo que vuelve a cero
+        // es la única señal de otra sesión; con cabecera, un retroceso es
+        // parte del mismo ensayo (p. ej. el reloj de la placa reanclado)
+        if !delimited && data_points.last().is_some_and(|last| time < last.time) {
+            new_session(&mut data_points, &mut propellant_mass, &mut metadata, false);
+        }
+
+        // Los canales ausentes en el archivo quedan como NaN
+        let values: Option<Vec<f64>> = columns
+            .iter()
+            .map(|column| match column {
+                Some(i) => parts.get(*i).and_then(|p| p.trim().parse::<f64>().ok()),
+                None => Some(f64::NAN),
+            })
+            .collect();
+
+        if let Some(values) = values {
+            data_points.push(DataPoint { time, values });
+        }
+    }
+
+    if !data_points.is_empty() {
+        sessions += 1;
+    } else if let Some(session) = previous {
+        (data_points, propellant_mass, metadata) = session;
+    } else {
+        return Err("No se encontraron datos válidos en el archivo CSV".to_string());
+    }
+
+    Ok(Dataset {
+        data_points,
+        propellant_mass,
+        engine: None,
+        masses: Vec::new(),
+        metadata,
+        sessions,
+    })
+}
+
+// CSV procesado: solo los datos, con el tiempo en segundos y una columna
+// por canal del esquema
+pub fn write_csv(schema: &Schema, data_points: &[DataPoint], path: &str) -> Result<(), String> {
+    let mut text = String::from(schema::SECONDS_COLUMN);
+    for channel in &schema.channels {
+        text.push(',');
+        text.push_str(&channel.name);
+    }
+    text.push('\n');
+    for point in data_points {
+        text.push_str(&format!("{:.6}", point.time));
+        for value in &point.values {
+            text.push_str(&format!(",{}", value));
+        }
+        text.push('\n');
+    }
+    std::fs::write(path, text).map_err(|e| format!("Error al guardar el CSV: {}", e))
+}
+
+// Tiempo entre la primera y la última muestra
+pub fn duration(data_points: &[DataPoint]) -> f64 {
+    match (data_points.first(), data_points.last()) {
+        (Some(first), Some(last)) => last.time - first.time,
+        _ => 0.0,
+    }
+}
+
+// Columna de un canal
+pub fn channel(data_points: &[DataPoint], index: usize) -> Vec<f64> {
+    data_points.iter().map(|dp| dp.values[index]).collect()
+}
+
+pub fn times(data_points: &[DataPoint]) -> Vec<f64> {
+    data_points.iter().map(|dp| dp.time).collect()
+}
+
+// Un motor RockSim se trata como un ensayo con solo el canal de empuje
+fn load_rse(schema: &Schema, path: &str) -> Result<Dataset, String> {
+    let curve = engine::read_rse(path)?;
+    let thrust_index = schema.thrust_index();
+    let point = |time: f64, thrust: f64| {
+        let mut values = vec![f64::NAN; schema.channels.len()];
+        values[thrust_index] = thrust;
+        DataPoint { time, values }
+    };
+
+    let mut data_points: Vec<DataPoint> = curve
+        .points
+        .iter()
+        .map(|&[time, thrust]| point(time, thrust))
+        .collect();
+    let mut masses: Vec<[f64; 2]> = curve
+        .points
+        .iter()
+        .zip(&curve.masses)
+        .map(|(&[time, _], &mass)| [time, mass])
+        .collect();
+
+    let propellant_mass = if curve.header.propellant_mass > 0.0 {
+        Some(curve.header.propellant_mass)
+    } else {
+        curve.masses.first().copied()
+    };
+
+    // La curva parte implícitamente en cero, con todo el propelente
+    if curve.points[0][0] > 0.0 {
+        data_points.insert(0, point(0.0, 0.0));
+        if !masses.is_empty() {
+            let full = propellant_mass.unwrap_or(masses[0][1]).max(masses[0][1]);
+            masses.insert(0, [0.0, full]);
+        }
+    }
+
+    Ok(Dataset {
+        data_points,
+        propellant_mass,
+        engine: Some(curve.header),
+        masses,
+        metadata: Metadata::default(),
+        sessions: 1,
+    })
+}
+
+pub fn parse_time_to_seconds(time_str: &str) -> Option<f64> {
+    let parts: Vec<&str> = time_str.split(':').collect();
+    if parts.len() == 4 {
+        if let (Ok(hours), Ok(minutes), Ok(seconds), Ok(millis)) = (
+            parts[0].parse::<f64>(),
+            parts[1].parse::<f64>(),
+            parts[2].parse::<f64>(),
+            parts[3].parse::<f64>(),
+        ) {
+            return Some(hours * 3600.0 + minutes * 60.0 + seconds + millis / 1000.0);
+        }
+    }
+    None
+}
+
+// Hora UTC en ISO-8601 con milisegundos, como en la columna UTC
+pub fn format_utc(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+// Formato HH:MM:SS:mmm de la columna Tiempo
+pub fn format_timestamp(elapsed: Duration) -> String {
+    format!(
+        "{:02}:{:02}:{:02}:{:03}",
+        elapsed.as_secs() / 3600,
+        (elapsed.as_secs() % 3600) / 60,
+        elapsed.as_secs() % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "Tiempo,Empuje,Temperatura Ambiente,Temperatura Tobera,UTC";
+
+    fn read(name: &str, text: &str) -> Dataset {
+        let path = std::env::temp_dir().join(format!("apogeo_{}_{}.csv", std::process::id(), name));
+        std::fs::write(&path, text).unwrap();
+        let dataset = read_csv(&Schema::default(), path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+        dataset.unwrap()
+    }
+
+    fn times(dataset: &Dataset) -> Vec<f64> {
+        dataset.data_points.iter().map(|p| p.time).collect()
+    }
+
+    #[test]
+    fn empty_session_does_not_leak_metadata() {
+        let text = format!(
+            "# inicio: 2026-01-01T10:00:00Z\n# operador: ANA\n# masa_propelente: 0.1 kg\n{h}\n\
+             # inicio: 2026-03-14T15:09:26Z\n# operador: Beto\n{h}\n\
+             00:00:00:010,1.0,20,20,\n00:00:00:020,2.0,20,20,\n",
+            h = HEADER
+        );
+        let dataset = read("empty_session", &text);
+
+        assert_eq!(dataset.sessions, 1);
+        assert_eq!(dataset.metadata.operator.as_deref(), Some("Beto"));
+        assert_eq!(
+            dataset.metadata.started_at.map(format_utc).as_deref(),
+            Some("2026-03-14T15:09:26.000Z")
+        );
+        assert_eq!(dataset.propellant_mass, None);
+        assert_eq!(times(&dataset), [0.01, 0.02]);
+    }
+
+    #[test]
+    fn last_session_wins() {
+        let text = format!(
+            "# inicio: 2026-01-01T10:00:00Z\n# masa_propelente: 0.1 kg\n{h}\n\
+             00:00:00:010,1.0,20,20,\n00:00:00:020,2.0,20,20,\n00:00:00:030,3.0,20,20,\n\
+             # inicio: 2026-01-01T11:00:00Z\n{h}\n00:00:00:010,5.0,20,20,\n",
+            h = HEADER
+        );
+        let dataset = read("last_session", &text);
+
+        assert_eq!(dataset.sessions, 2);
+        assert_eq!(dataset.propellant_mass, None);
+        assert_eq!(times(&dataset), [0.01]);
+    }
+
+    #[test]
+    fn trailing_empty_session_keeps_previous() {
+        let text = format!(
+            "# inicio: 2026-01-01T10:00:00Z\n# operador: Ana\n{h}\n00:00:00:010,1.0,20,20,\n\
+             # inicio: 2026-01-01T11:00:00Z\n# operador: Beto\n{h}\n",
+            h = HEADER
+        );
+        let dataset = read("trailing_empty", &text);
+
+        assert_eq!(dataset.sessions, 1);
+        assert_eq!(dataset.metadata.operator.as_deref(), Some("Ana"));
+        assert_eq!(times(&dataset), [0.01]);
+    }
+
+    #[test]
+    fn backstep_within_session_is_kept() {
+        let text = format!(
+            "# inicio: 2026-01-01T10:00:00Z\n{}\n\
+             00:00:10:010,1.0,20,20,\n00:00:09:990,2.0,20,20,\n00:00:10:020,3.0,20,20,\n",
+            HEADER
+        );
+        let dataset = read("backstep", &text);
+
+        assert_eq!(dataset.sessions, 1);
+        assert_eq!(dataset.data_points.len(), 3);
+    }
+
+    #[test]
+    fn headerless_log_splits_on_time_reset() {
+        let text = "00:00:00:010,1.0,20,20\n00:00:00:020,2.0,20,20\n00:00:00:005,3.0,20,20\n";
+        let dataset = read("headerless", text);
+
+        assert_eq!(dataset.sessions, 2);
+        assert_eq!(times(&dataset), [0.005]);
+    }
+
+    #[test]
+    fn header_is_matched_on_first_column() {
+        let text = "Tiempo,Empuje,Temperatura Tobera,Temperatura Ambiente,Nota\n\
+             00:00:00:010,1.0,30,20,Tiempo muerto\n00:00:00:020,2.0,31,20,\n";
+        let dataset = read("first_column", text);
+
+        assert_eq!(dataset.sessions, 1);
+        assert_eq!(dataset.data_points.len(), 2);
+        // Las columnas se toman por nombre, no por posición
+        assert_eq!(dataset.data_points[0].values, [1.0, 20.0, 30.0]);
+    }
+
+    #[test]
+    fn header_lines_round_trip() {
+        let metadata = Metadata {
+            operator: Some("Ana".to_string()),
+            notes: Some("viento\ncalmo".to_string()),
+            ..Metadata::default()
+        };
+        let mut lines = metadata.header_lines();
+        lines.push(format!("{} 0.25 kg", PROPELLANT_MASS_TAG));
+        let (read, mass) = read_header(&lines);
+
+        assert_eq!(read.operator.as_deref(), Some("Ana"));
+        assert_eq!(read.notes.as_deref(), Some("viento\ncalmo"));
+        assert_eq!(mass, Some(0.25));
+    }
+}
@@ -0,0 +1,395 @@
+// Exportación de curvas de empuje para simuladores de vuelo.
+//
+// Formato RASP (.eng), el que leen OpenRocket y RockSim:
+//
+//   ; comentario
+//   H128 29 194 7 0.0920 0.2060 Coheteria_Beauchef
+//      0.010   45.300
+//      ...
+//      1.870    0.000
+//
+// La cabecera es: nombre, diámetro (mm), largo (mm), retardos separados por
+// '-' ("P" si el motor está tapado), masa de propelente (kg), masa total
+// (kg) y fabricante. Los tiempos se cuentan desde la ignición y el último
+// punto debe tener empuje cero.
+//
+// Formato RockSim (.rse), XML con masa por punto:
+//
+//   <engine-database><engine-list>
+//     <engine code="H128" mfg="..." dia="29" len="194" initWt="206"
+//             propWt="92" delays="7" ...>
+//       <data>
+//         <eng-data t="0.010" f="45.300" m="91.8" cg="97"/>
+//       </data>
+//     </engine>
+//   </engine-list></engine-database>
+//
+// En .rse las masas van en gramos y `m` es la masa de propelente que queda
+// en ese instante.
+
+use crate::analysis;
+use std::io::Write;
+
+// Límite habitual de puntos de los lectores RASP más antiguos
+pub const DEFAULT_MAX_POINTS: usize = 32;
+
+#[derive(Clone)]
+pub struct EngineHeader {
+    pub name: String,
+    pub diameter: f64, // mm
+    pub length: f64,   // mm
+    pub delays: String,
+    pub propellant_mass: f64, // kg
+    pub total_mass: f64,      // kg
+    pub manufacturer: String,
+}
+
+impl Default for EngineHeader {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            diameter: 29.0,
+            length: 100.0,
+            delays: "P".to_string(),
+            propellant_mass: 0.0,
+            total_mass: 0.0,
+            manufacturer: "Coheteria_Beauchef".to_string(),
+        }
+    }
+}
+
+// Curva de la combustión con el tiempo contado desde la ignición. Termina en
+// el apagado con empuje cero, como pide el formato
+pub fn burn_curve(times: &[f64], thrust: &[f64], ignition: f64, burnout: f64) -> Vec<[f64; 2]> {
+    let mut points: Vec<[f64; 2]> = times
+        .iter()
+        .zip(thrust.iter())
+        .filter(|(&t, f)| t > ignition && t < burnout && f.is_finite())
+        .map(|(&t, &f)| [t - ignition, f.max(0.0)])
+        .collect();
+    points.push([burnout - ignition, 0.0]);
+    points
+}
+
+// Reduce la curva a `max_points` agregando, desde los extremos, el punto que
+// más se aleja de la curva ya simplificada
+pub fn reduce_points(points: &[[f64; 2]], max_points: usize) -> Vec<[f64; 2]> {
+    if points.len() <= max_points || max_points < 2 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    for _ in 2..max_points {
+        let mut best: Option<(usize, f64)> = None;
+        let mut start = 0;
+        for end in 1..points.len() {
+            if !keep[end] {
+                continue;
+            }
+            let [t0, f0] = points[start];
+            let [t1, f1] = points[end];
+            for (i, &[t, f]) in points.iter().enumerate().take(end).skip(start + 1) {
+                let line = if t1 > t0 {
+                    f0 + (f1 - f0) * (t - t0) / (t1 - t0)
+                } else {
+                    f0
+                };
+                let error = (f - line).abs();
+                if best.is_none_or(|(_, e)| error > e) {
+                    best = Some((i, error));
+                }
+            }
+            start = end;
+        }
+
+        match best {
+            Some((i, _)) => keep[i] = true,
+            None => break,
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(p, _)| *p)
+        .collect()
+}
+
+pub fn write_eng(path: &str, header: &EngineHeader, points: &[[f64; 2]]) -> Result<(), String> {
+    let mut file = std::fs::File::create(path)
+        .map_err(|e| format!("Error al crear el archivo .eng: {}", e))?;
+
+    // El formato separa los campos con espacios
+    let field = |text: &str, default: &str| {
+        let text: Vec<&str> = text.split_whitespace().collect();
+        if text.is_empty() {
+            default.to_string()
+        } else {
+            text.join("_")
+        }
+    };
+
+    let mut text = String::new();
+    text.push_str(&format!(
+        "; {} - exportado por Apogeo\n",
+        field(&header.name, "Motor")
+    ));
+    text.push_str(&format!(
+        "{} {} {} {} {:.4} {:.4} {}\n",
+        field(&header.name, "Motor"),
+        header.diameter,
+        header.length,
+        field(&header.delays, "P"),
+        header.propellant_mass,
+        header.total_mass,
+        field(&header.manufacturer, "Apogeo")
+    ));
+    for [time, thrust] in points {
+        text.push_str(&format!("   {:.3} {:.3}\n", time, thrust));
+    }
+    text.push_str(";\n");
+
+    file.write_all(text.as_bytes())
+        .map_err(|e| format!("Error al escribir el archivo .eng: {}", e))
+}
+
+// Curva leída desde un archivo de motor
+pub struct EngineCurve {
+    pub header: EngineHeader,
+    pub points: Vec<[f64; 2]>, // tiempo (s), empuje (N)
+    // Propelente restante en cada punto (kg). Un .eng no lo trae y se
+    // estima; en un .rse queda vacío si los puntos no tienen `m`
+    pub masses: Vec<f64>,
+}
+
+// Impulso acumulado hasta cada punto; la curva parte implícitamente de (0, 0)
+fn cumulative_impulse(points: &[[f64; 2]]) -> Vec<f64> {
+    let mut previous = [0.0, 0.0];
+    let mut total = 0.0;
+    points
+        .iter()
+        .map(|&point| {
+            total += (previous[1] + point[1]) / 2.0 * (point[0] - previous[0]);
+            previous = point;
+            total
+        })
+        .collect()
+}
+
+// Lee la curva de un archivo .eng; si trae varios motores se usa el primero
+pub fn read_eng(path: &str) -> Result<EngineCurve, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error al leer el archivo .eng: {}", e))?;
+
+    let mut lines = text
+        .lines()
+        .map(|l| l.split(';').next().unwrap_or("").trim())
+        .filter(|l| !l.is_empty());
+
+    let fields: Vec<&str> = lines
+        .next()
+        .ok_or("El archivo .eng está vacío")?
+        .split_whitespace()
+        .collect();
+    if fields.len() < 7 {
+        return Err("Cabecera .eng incompleta".to_string());
+    }
+    let number = |i: usize| fields[i].parse::<f64>().unwrap_or(0.0);
+    let header = EngineHeader {
+        name: fields[0].to_string(),
+        diameter: number(1),
+        length: number(2),
+        delays: fields[3].to_string(),
+        propellant_mass: number(4),
+        total_mass: number(5),
+        manufacturer: fields[6..].join(" "),
+    };
+
+    let mut points = Vec::new();
+    for line in lines {
+        let values: Vec<f64> = line
+            .split_whitespace()
+            .map_while(|v| v.parse().ok())
+            .collect();
+        if values.len() != 2 {
+            // Cabecera del siguiente motor
+            break;
+        }
+        points.push([values[0], values[1]]);
+    }
+    if points.is_empty() {
+        return Err("El motor del .eng no tiene puntos".to_string());
+    }
+
+    let masses = propellant_masses(&points, header.propellant_mass);
+    Ok(EngineCurve {
+        header,
+        points,
+        masses,
+    })
+}
+
+// Propelente restante en cada punto, suponiendo que se consume en
+// proporción al impulso entregado
+pub fn propellant_masses(points: &[[f64; 2]], propellant_mass: f64) -> Vec<f64> {
+    let cumulative = cumulative_impulse(points);
+    let total = cumulative.last().copied().unwrap_or(0.0);
+
+    cumulative
+        .iter()
+        .map(|&burned| {
+            if total > 0.0 {
+                propellant_mass * (1.0 - burned / total)
+            } else {
+                propellant_mass
+            }
+        })
+        .collect()
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Propelente restante [tiempo, kg] interpolado en cada tiempo; antes del
+// primer punto y después del último se mantienen los extremos
+pub fn masses_at(masses: &[[f64; 2]], times: &[f64]) -> Vec<f64> {
+    times
+        .iter()
+        .map(|&time| {
+            analysis::interpolate_curve(masses, time).unwrap_or_else(|| {
+                match (masses.first(), masses.last()) {
+                    (Some(first), _) if time < first[0] => first[1],
+                    (_, Some(last)) => last[1],
+                    _ => 0.0,
+                }
+            })
+        })
+        .collect()
+}
+
+// Con `masses` (una por punto, p. ej. importadas de otro .rse) se escriben
+// tal cual; si no, se estiman a partir del impulso
+pub fn write_rse(
+    path: &str,
+    header: &EngineHeader,
+    points: &[[f64; 2]],
+    masses: Option<&[f64]>,
+) -> Result<(), String> {
+    let mut file = std::fs::File::create(path)
+        .map_err(|e| format!("Error al crear el archivo .rse: {}", e))?;
+
+    let masses = match masses {
+        Some(masses) if masses.len() == points.len() => masses.to_vec(),
+        _ => propellant_masses(points, header.propellant_mass),
+    };
+    let total_impulse = cumulative_impulse(points).last().copied().unwrap_or(0.0);
+    let burn_time = points.last().map_or(0.0, |p| p[0]);
+    let peak_thrust = points.iter().map(|p| p[1]).fold(0.0, f64::max);
+    let average_thrust = if burn_time > 0.0 {
+        total_impulse / burn_time
+    } else {
+        0.0
+    };
+    let cg = header.length / 2.0;
+
+    let mut text = String::new();
+    text.push_str("<engine-database>\n  <engine-list>\n");
+    text.push_str(&format!(
+        "    <engine code=\"{}\" mfg=\"{}\" Type=\"single-use\" dia=\"{}\" len=\"{}\" \
+         initWt=\"{:.1}\" propWt=\"{:.1}\" delays=\"{}\" auto-calc-mass=\"0\" \
+         auto-calc-cg=\"0\" avgThrust=\"{:.3}\" peakThrust=\"{:.3}\" Itot=\"{:.3}\" \
+         burn-time=\"{:.3}\">\n",
+        xml_escape(&header.name),
+        xml_escape(&header.manufacturer),
+        header.diameter,
+        header.length,
+        header.total_mass * 1000.0,
+        header.propellant_mass * 1000.0,
+        xml_escape(&header.delays),
+        average_thrust,
+        peak_thrust,
+        total_impulse,
+        burn_time
+    ));
+    text.push_str("      <comments>Exportado por Apogeo</comments>\n");
+    text.push_str("      <data>\n");
+    for ([time, thrust], mass) in points.iter().zip(masses) {
+        text.push_str(&format!(
+            "        <eng-data t=\"{:.4}\" f=\"{:.3}\" m=\"{:.3}\" cg=\"{}\"/>\n",
+            time,
+            thrust,
+            mass * 1000.0,
+            cg
+        ));
+    }
+    text.push_str("      </data>\n    </engine>\n  </engine-list>\n</engine-database>\n");
+
+    file.write_all(text.as_bytes())
+        .map_err(|e| format!("Error al escribir el archivo .rse: {}", e))
+}
+
+// Lee el primer motor de un archivo .rse
+pub fn read_rse(path: &str) -> Result<EngineCurve, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error al leer el archivo .rse: {}", e))?;
+    let document =
+        roxmltree::Document::parse(&text).map_err(|e| format!("Archivo .rse inválido: {}", e))?;
+
+    let engine = document
+        .descendants()
+        .find(|n| n.has_tag_name("engine"))
+        .ok_or("El archivo .rse no contiene motores")?;
+    let number = |node: roxmltree::Node, name: &str| -> f64 {
+        node.attribute(name)
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0.0)
+    };
+
+    let header = EngineHeader {
+        name: engine.attribute("code").unwrap_or("").to_string(),
+        diameter: number(engine, "dia"),
+        length: number(engine, "len"),
+        delays: engine.attribute("delays").unwrap_or("").to_string(),
+        propellant_mass: number(engine, "propWt") / 1000.0,
+        total_mass: number(engine, "initWt") / 1000.0,
+        manufacturer: engine.attribute("mfg").unwrap_or("").to_string(),
+    };
+
+    let mut points = Vec::new();
+    let mut masses = Vec::new();
+    let mut has_masses = false;
+    for node in engine.descendants().filter(|n| n.has_tag_name("eng-data")) {
+        let (time, thrust) = match (node.attribute("t"), node.attribute("f")) {
+            (Some(t), Some(f)) => match (t.trim().parse(), f.trim().parse()) {
+                (Ok(t), Ok(f)) => (t, f),
+                _ => return Err("Punto con valores no numéricos en el .rse".to_string()),
+            },
+            _ => return Err("Punto sin tiempo o empuje en el .rse".to_string()),
+        };
+        points.push([time, thrust]);
+        has_masses |= node.attribute("m").is_some();
+        masses.push(number(node, "m") / 1000.0);
+    }
+    if !has_masses {
+        masses.clear();
+    }
+
+    if points.is_empty() {
+        return Err("El motor del .rse no tiene puntos".to_string());
+    }
+
+    Ok(EngineCurve {
+        header,
+        points,
+        masses,
+    })
+}
@@ -0,0 +1,215 @@
+// Reensamblado de registros ASCII terminados en salto de línea.
+//
+// El puerto entrega bytes en trozos arbitrarios: una lectura puede traer
+// media línea o varias líneas juntas. LineFramer acumula los bytes y solo
+// entrega registros completos, contando los que se descartan.
+
+use crate::binary::{self, BinaryFramer};
+
+// Largo máximo de una línea antes de considerarla sobredimensionada
+pub const MAX_LINE_LEN: usize = 256;
+
+#[derive(Clone, Default)]
+pub struct FrameStats {
+    pub frames: u64,     // registros válidos entregados
+    pub malformed: u64,  // campos no numéricos, sobrantes o texto inválido
+    pub truncated: u64,  // menos campos de los esperados
+    pub oversize: u64,   // registros que superan el largo máximo
+    pub crc_errors: u64, // paquetes binarios con CRC inválido
+    pub lost: u64,       // paquetes binarios perdidos según la secuencia
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum WireFormat {
+    Ascii,
+    Binary,
+}
+
+impl WireFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            WireFormat::Ascii => "ASCII (CSV)",
+            WireFormat::Binary => "Binario (CRC-16)",
+        }
+    }
+
+    // Registro tal como lo envía la placa; la secuencia solo se usa en el
+    // formato binario y el contador, si lo hay, va como primer campo. Falla
+    // si el registro no entra en un paquete binario
+    pub fn encode(
+        &self,
+        sequence: u16,
+        counter: Option<u32>,
+        values: &[f64],
+    ) -> Result<Vec<u8>, String> {
+        match self {
+            WireFormat::Ascii => {
+                let mut fields: Vec<String> = counter.iter().map(|c| c.to_string()).collect();
+                fields.extend(values.iter().map(|v| v.to_string()));
+                Ok(format!("{}\n", fields.join(",")).into_bytes())
+            }
+            WireFormat::Binary => binary::encode(sequence, counter, values),
+        }
+    }
+}
+
+// Decodificador del formato elegido en la configuración. Con `counter`
+// cada registro trae antes de los canales el reloj de la placa, que se
+// entrega como primer valor
+pub enum Decoder {
+    Ascii(LineFramer),
+    Binary(BinaryFramer),
+}
+
+impl Decoder {
+    pub fn new(format: WireFormat, channels: usize, counter: bool) -> Self {
+        let expected_fields = channels + usize::from(counter);
+        match format {
+            WireFormat::Ascii => Decoder::Ascii(LineFramer::new(expected_fields)),
+            WireFormat::Binary => Decoder::Binary(BinaryFramer::new(expected_fields, counter)),
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Vec<f64>> {
+        match self {
+            Decoder::Ascii(framer) => framer.push(bytes),
+            Decoder::Binary(framer) => framer.push(bytes),
+        }
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        match self {
+            Decoder::Ascii(framer) => &framer.stats,
+            Decoder::Binary(framer) => &framer.stats,
+        }
+    }
+}
+
+pub struct LineFramer {
+    buffer: Vec<u8>,
+    expected_fields: usize,
+    // Tras una línea sobredimensionada se ignora todo hasta el próximo '\n'
+    discarding: bool,
+    pub stats: FrameStats,
+}
+
+impl LineFramer {
+    pub fn new(expected_fields: usize) -> Self {
+        Self {
+            buffer: Vec::with_capacity(MAX_LINE_LEN),
+            expected_fields,
+            discarding: false,
+            stats: FrameStats::default(),
+        }
+    }
+
+    // Agrega bytes recibidos y devuelve los registros completos que se
+    // pudieron decodificar, en orden de llegada
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Vec<f64>> {
+        let mut records = Vec::new();
+
+        for &byte in bytes {
+            if byte == b'\n' {
+                if self.discarding {
+                    self.discarding = false;
+                } else if let Some(values) = self.parse_line() {
+                    records.push(values);
+                }
+                self.buffer.clear();
+                continue;
+            }
+
+            if self.discarding {
+                continue;
+            }
+
+            self.buffer.push(byte);
+            if self.buffer.len() > MAX_LINE_LEN {
+                self.stats.oversize += 1;
+                self.buffer.clear();
+                self.discarding = true;
+            }
+        }
+
+        records
+    }
+
+    fn parse_line(&mut self) -> Option<Vec<f64>> {
+        let line = match std::str::from_utf8(&self.buffer) {
+            Ok(line) => line.trim(),
+            Err(_) => {
+                self.stats.malformed += 1;
+                return None;
+            }
+        };
+
+        if line.is_empty() {
+            return None;
+        }
+
+        let parts: Vec<&str> = line.split(',').collect();
+        if parts.len() < self.expected_fields {
+            self.stats.truncated += 1;
+            return None;
+        }
+        if parts.len() > self.expected_fields {
+            self.stats.malformed += 1;
+            return None;
+        }
+
+        let values: Result<Vec<f64>, _> = parts.iter().map(|p| p.trim().parse::<f64>()).collect();
+        match values {
+            Ok(values) => {
+                self.stats.frames += 1;
+                Some(values)
+            }
+            Err(_) => {
+                self.stats.malformed += 1;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassembles_partial_lines() {
+        let mut framer = LineFramer::new(3);
+
+        assert!(framer.push(b"1.5,2").is_empty());
+        assert_eq!(framer.push(b"0,3\n4,5,"), [[1.5, 20.0, 3.0]]);
+        assert_eq!(
+            framer.push(b"6\r\n7,8,9\n"),
+            [[4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]
+        );
+        assert_eq!(framer.stats.frames, 3);
+    }
+
+    #[test]
+    fn counts_rejected_lines() {
+        let mut framer = LineFramer::new(3);
+        let mut bytes = b"1,2\n1,2,3,4\n1,x,3\n\n".to_vec();
+        bytes.extend(vec![b'9'; MAX_LINE_LEN + 10]);
+        bytes.extend(b"\n1,2,3\n");
+
+        let records = framer.push(&bytes);
+
+        assert_eq!(records, [[1.0, 2.0, 3.0]]);
+        assert_eq!(framer.stats.truncated, 1);
+        assert_eq!(framer.stats.malformed, 2);
+        assert_eq!(framer.stats.oversize, 1);
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        for format in [WireFormat::Ascii, WireFormat::Binary] {
+            let mut decoder = Decoder::new(format, 2, true);
+            let bytes = format.encode(0, Some(123_456), &[1.5, -2.25]).unwrap();
+
+            assert_eq!(decoder.push(&bytes), [[123_456.0, 1.5, -2.25]]);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+// Historial completo de una sesión en vivo.
+//
+// Conserva todas las muestras y, al lado, una pirámide de mínimos y máximos
+// por canal: el nivel 0 resume bloques de BLOCK muestras y cada nivel
+// siguiente resume pares del anterior. Para dibujar un tramo se elige el
+// nivel más fino que entra en el presupuesto de puntos y se toman el mínimo
+// y el máximo de cada bloque, así los picos no desaparecen al alejar la
+// vista. La pirámide ocupa cerca de 4/BLOCK índices por muestra y canal, y
+// agregar una muestra cuesta O(log n).
+
+use crate::dataset::DataPoint;
+use std::ops::{Deref, Range};
+
+// Muestras por bloque del nivel 0
+const BLOCK: usize = 16;
+
+// Extremos de un bloque, como índices de muestra
+#[derive(Clone, Copy)]
+struct Extremes {
+    min: usize,
+    max: usize,
+}
+
+#[derive(Default)]
+pub struct History {
+    points: Vec<DataPoint>,
+    // [canal][nivel][bloque]
+    levels: Vec<Vec<Vec<Extremes>>>,
+}
+
+impl From<Vec<DataPoint>> for History {
+    fn from(points: Vec<DataPoint>) -> Self {
+        let mut history = History::default();
+        for point in points {
+            history.push(point);
+        }
+        history
+    }
+}
+
+// Las muestras se leen como un slice común
+impl Deref for History {
+    type Target = [DataPoint];
+
+    fn deref(&self) -> &[DataPoint] {
+        &self.points
+    }
+}
+
+impl History {
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.levels.clear();
+    }
+
+    pub fn push(&mut self, point: DataPoint) {
+        let index = self.points.len();
+        let channels = point.values.len();
+        self.points.push(point);
+        if self.levels.len() < channels {
+            self.levels.resize_with(channels, || vec![Vec::new()]);
+        }
+
+        for channel in 0..channels {
+            let points = &self.points;
+            let levels = &mut self.levels[channel];
+
+            let block = index / BLOCK;
+            match levels[0].get_mut(block) {
+                Some(extremes) => {
+                    *extremes = merge(points, channel, *extremes, index, index);
+                }
+                None => levels[0].push(Extremes {
+                    min: index,
+                    max: index,
+                }),
+            }
+
+            // Se rehace el bloque que contiene al modificado en cada nivel
+            let mut level = 0;
+            let mut block = block;
+            while levels[level].len() > 1 {
+                if levels.len() == level + 1 {
+                    levels.push(Vec::new());
+                }
+                let parent = block / 2;
+                let left = levels[level][parent * 2];
+                let merged = match levels[level].get(parent * 2 + 1) {
+                    Some(right) => merge(points, channel, left, right.min, right.max),
+                    None => left,
+                };
+                match levels[level + 1].get_mut(parent) {
+                    Some(extremes) => *extremes = merged,
+                    None => levels[level + 1].push(merged),
+                }
+                level += 1;
+                block = parent;
+            }
+        }
+    }
+
+    // Índices de las muestras con tiempo dentro de [start, end]
+    pub fn range(&self, start: f64, end: f64) -> Range<usize> {
+        let first = self.points.partition_point(|dp| dp.time < start);
+        let last = self.points.partition_point(|dp| dp.time <= end);
+        first..last.max(first)
+    }
+
+    // Promedio de un canal en los últimos `seconds` segundos; con él se
+    // calcula la tara, tanto en vivo como al reprocesar una captura
+    pub fn average(&self, channel: usize, seconds: f64) -> Option<f64> {
+        let last_time = self.points.last()?.time;
+        let window = &self.points[self.range(last_time - seconds, last_time)];
+        let sum: f64 = window.iter().map(|dp| dp.values[channel]).sum();
+        Some(sum / window.len() as f64)
+    }
+
+    // Índices de las muestras a dibujar de un canal, en orden, sin pasar de
+    // unos `max_points` aproximados. Si el tramo entra completo se devuelve
+    // tal cual; si no, el mínimo y el máximo de cada bloque
+    pub fn decimate(&self, channel: usize, range: Range<usize>, max_points: usize) -> Vec<usize> {
+        let range = range.start..range.end.min(self.points.len());
+        if range.len() <= max_points || channel >= self.levels.len() {
+            return range.collect();
+        }
+
+        // Cada bloque aporta dos puntos
+        let mut level = 0;
+        let mut size = BLOCK;
+        while level + 1 < self.levels[channel].len() && range.len() / size * 2 > max_points {
+            level += 1;
+            size *= 2;
+        }
+
+        let blocks = &self.levels[channel][level];
+        let first = range.start / size;
+        let last = ((range.end - 1) / size).min(blocks.len() - 1);
+        let mut indices = Vec::with_capacity((last - first + 1) * 2);
+        for extremes in &blocks[first..=last] {
+            let (a, b) = if extremes.min <= extremes.max {
+                (extremes.min, extremes.max)
+            } else {
+                (extremes.max, extremes.min)
+            };
+            indices.push(a);
+            if b != a {
+                indices.push(b);
+            }
+        }
+        indices
+    }
+}
+
+// Combina los extremos de un bloque con los de otro (o con una muestra, si
+// `min` y `max` son el mismo índice). Los valores ausentes (NaN) pierden
+// siempre contra uno presente
+fn merge(points: &[DataPoint], channel: usize, a: Extremes, min: usize, max: usize) -> Extremes {
+    let value = |i: usize| points[i].values[channel];
+    Extremes {
+        min: if value(min) < value(a.min) || value(a.min).is_nan() {
+            min
+        } else {
+            a.min
+        },
+        max: if value(max) > value(a.max) || value(a.max).is_nan() {
+            max
+        } else {
+            a.max
+        },
+    }
+}
@@ -0,0 +1,50 @@
+// Biblioteca de Apogeo: lectura de ensayos, adquisición y métricas del motor.
+//
+// La interfaz gráfica y la línea de comandos usan estos módulos, y otras
+// herramientas del equipo (simulador de trayectoria, generador de informes)
+// pueden usarlos para calcular impulso y tiempos de combustión exactamente
+// igual que Apogeo:
+//
+//   let schema = apogeo::schema::Schema::load("esquema.toml")?;
+//   let dataset = apogeo::dataset::load(&schema, "ensayo.csv")?;
+//   let report = apogeo::report::Report::new(
+//       "ensayo.csv",
+//       &schema,
+//       &dataset.data_points,
+//       apogeo::analysis::BurnThresholds::default(),
+//       dataset.propellant_mass.unwrap_or(0.0),
+//       "",
+//   );
+//
+// Módulos:
+//   - schema, dataset: canales del banco y lectura de registros (muestras)
+//   - analysis, report: métricas de la combustión y resumen del ensayo
+//   - engine: curvas de motor RASP (.eng) y RockSim (.rse)
+//   - calibration, thermocouple: conversión de la celda y los termopares
+//   - framing, binary: decodificación de los formatos del cable
+//   - clock: base de tiempo con el reloj de la placa
+//   - source, acquisition, simulator: fuentes de datos y registro en vivo
+//     (feature `acquisition`, que trae los puertos serie)
+//   - capture: captura cruda del enlace para reprocesar ensayos
+//   - history: sesión completa en memoria con resúmenes para los gráficos
+//   - session: carpetas de ensayo con registro, metadatos y resumen
+
+#[cfg(feature = "acquisition")]
+pub mod acquisition;
+pub mod analysis;
+pub mod binary;
+pub mod calibration;
+pub mod capture;
+pub mod clock;
+pub mod dataset;
+pub mod engine;
+pub mod framing;
+pub mod history;
+pub mod report;
+pub mod schema;
+pub mod session;
+#[cfg(feature = "acquisition")]
+pub mod simulator;
+#[cfg(feature = "acquisition")]
+pub mod source;
+pub mod thermocouple;
@@ -1,31 +1,67 @@
+mod cli;
+
+use apogeo::acquisition::{AcquisitionConfig, SharedState};
+use apogeo::analysis::{BurnMetrics, BurnThresholds, MotorClass};
+use apogeo::calibration::{
+    self, CalibrationPoint, CalibrationProfile, CalibrationStore, MAX_DEGREE,
+};
+use apogeo::clock::ClockStats;
+use apogeo::dataset::{DataPoint, Metadata};
+use apogeo::engine::EngineHeader;
+use apogeo::framing::{FrameStats, WireFormat};
+use apogeo::history::History;
+use apogeo::report::Report;
+use apogeo::schema::{PlotGroup, Schema};
+use apogeo::session::{Session, SessionInfo};
+use apogeo::source::{LinkState, SourceConfig, SourceKind, SourceStatus};
+use apogeo::{acquisition, analysis, capture, dataset, engine, schema, simulator};
 use eframe::egui;
 use egui::RichText;
-use egui_plot::{Line, Plot, PlotPoints};
+use egui_plot::{Legend, Line, Plot, PlotPoints, VLine};
 use image::GenericImageView;
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use std::ops::Range;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
-
-#[derive(Clone)]
-struct DataPoint {
-    time: f64,         // tiempo en segundos
-    thrust: f64,       // empuje
-    temp_ambient: f64, // temperatura ambiente
-    temp_nozzle: f64,  // temperatura tobera
-}
+use std::time::Instant;
 
 struct App {
     last_data: Arc<Mutex<String>>,
     running: Arc<Mutex<bool>>,
     start_time: Instant,
-    data_points: Arc<Mutex<Vec<DataPoint>>>,
+    data_points: Arc<Mutex<History>>,
+    frame_stats: Arc<Mutex<FrameStats>>,
+    source_status: Arc<Mutex<SourceStatus>>,
+    clock_stats: Arc<Mutex<Option<ClockStats>>>,
+
+    // Esquema de canales activo
+    schema: Schema,
+    schema_path: String,
+
+    // Calibración de la celda de carga
+    calibration_path: String,
+    calibrations: CalibrationStore,
+    active_calibration: String, // vacío si el empuje llega sin calibrar
+    raw_thrust: Arc<Mutex<Vec<f64>>>, // últimas lecturas crudas de empuje
+    wizard: CalibrationWizard,
+
+    // Tara del empuje durante el monitoreo
+    tare_seconds: f64,                     // ventana que se promedia
+    tare_request: Arc<Mutex<Option<f64>>>, // offset pendiente de aplicar
+    tare_offset: Arc<Mutex<f64>>,          // offset acumulado de la sesión
+
+    // Sesiones de ensayo: cada "Iniciar" crea una carpeta en `sessions_root`
+    sessions_root: String,
+    session_form: SessionInfo, // motor, lote, operador, clima y notas
+    session: Option<Session>,  // sesión en curso
+    sessions: Vec<Session>,    // sesiones del explorador
+    markers: Arc<Mutex<Vec<String>>>,
+    marker_text: String,
 
     // Campos para la configuración
-    port_name: String,
-    baud_rate: u32,
-    file_path: String,
+    source: SourceConfig,
+    wire_format: WireFormat,
+    file_path: String, // registro de la sesión en curso
     available_ports: Vec<String>,
     configured: bool,
     serial_thread: Option<thread::JoinHandle<()>>,
@@ -37,39 +73,199 @@ struct App {
     // Nuevos campos para carga de CSV
     csv_file_path: String,
     csv_data_loaded: bool,
+    csv_metadata: Metadata, // cabecera del registro abierto
+    csv_sessions: usize,    // sesiones del registro; se muestra la última
     total_impulse: f64,
+    propellant_mass: f64, // kg; 0 si no se conoce
+    burn_thresholds: BurnThresholds,
+    burn: Option<BurnMetrics>, // ignición y apagado detectados
+    motor_delay: String,       // retardo de la designación; vacío si no tiene
+    motor_export: MotorExport,
+
+    // Curva de referencia superpuesta al empuje
+    reference_path: String,
+    reference: Option<ReferenceCurve>,
+    reference_tracker: ReferenceTracker,
+
+    // Ventana de los gráficos en vivo en segundos; None muestra toda la sesión
+    live_window: Option<f64>,
+
+    // Comparación de varios ensayos
+    comparison_paths: String, // un archivo por línea
+    comparison: Vec<TestRun>,
     current_mode: AppMode,
 
     // Nuevos campos para controlar los paneles
     show_csv_panel: bool,
     show_serial_panel: bool,
+    show_comparison_panel: bool,
+    show_sessions_panel: bool,
     error_message: String,
 }
 
+// Ensayo cargado en la comparación
+struct TestRun {
+    name: String,
+    data_points: Vec<DataPoint>,
+    burn: Option<BurnMetrics>,
+    propellant_mass: f64, // kg; 0 si no se conoce
+}
+
+impl TestRun {
+    fn isp(&self) -> Option<f64> {
+        analysis::specific_impulse(self.burn?.impulse, self.propellant_mass)
+    }
+}
+
+// Colores de los ensayos, iguales en todos los gráficos
+const RUN_COLORS: [egui::Color32; 8] = [
+    egui::Color32::from_rgb(0, 150, 255),
+    egui::Color32::from_rgb(255, 120, 0),
+    egui::Color32::from_rgb(0, 190, 90),
+    egui::Color32::from_rgb(220, 50, 50),
+    egui::Color32::from_rgb(160, 90, 220),
+    egui::Color32::from_rgb(200, 170, 0),
+    egui::Color32::from_rgb(0, 190, 190),
+    egui::Color32::from_rgb(230, 80, 170),
+];
+
+// Puntos por canal que se dibujan como máximo; las sesiones largas se
+// resumen con los mínimos y máximos de cada tramo
+const PLOT_POINTS: usize = 2000;
+
+// Ventanas de los gráficos en vivo; None muestra toda la sesión
+const LIVE_WINDOWS: [(&str, Option<f64>); 4] = [
+    ("5 s", Some(5.0)),
+    ("30 s", Some(30.0)),
+    ("2 min", Some(120.0)),
+    ("Todo", None),
+];
+
+// Curva con la que se comparan los datos
+struct ReferenceCurve {
+    name: String,
+    points: Vec<[f64; 2]>, // tiempo desde la ignición (s), empuje (N)
+    peak_thrust: f64,
+}
+
+// Avance de la comparación con la referencia. Recuerda hasta qué muestra se
+// buscó la ignición y hasta cuál se acumuló la desviación, así cada cuadro
+// solo procesa las muestras nuevas
+#[derive(Default)]
+struct ReferenceTracker {
+    live_ignition: Option<f64>, // ignición detectada en vivo para alinear
+    ignition_scan: usize,       // muestras ya revisadas buscando la ignición
+    ignition: Option<f64>,      // ignición con la que se acumuló la desviación
+    compared: usize,            // muestras ya acumuladas
+    deviation: analysis::RunningDeviation,
+}
+
+// Formulario de exportación de la curva del motor (.eng / .rse)
+struct MotorExport {
+    open: bool,
+    path: String,
+    header: EngineHeader,
+    max_points: usize,
+    masses: Vec<[f64; 2]>, // propelente por punto del .rse abierto, si lo trae
+    message: String,
+}
+
+impl Default for MotorExport {
+    fn default() -> Self {
+        Self {
+            open: false,
+            path: "motor".to_string(), // la extensión depende del formato
+            header: EngineHeader::default(),
+            max_points: engine::DEFAULT_MAX_POINTS,
+            masses: Vec::new(),
+            message: String::new(),
+        }
+    }
+}
+
+// Estado del asistente de calibración
+struct CalibrationWizard {
+    open: bool,
+    name: String,
+    mass: f64,
+    raw: f64,
+    degree: usize,
+    points: Vec<CalibrationPoint>,
+    result: Option<CalibrationProfile>,
+    message: String,
+}
+
+impl Default for CalibrationWizard {
+    fn default() -> Self {
+        Self {
+            open: false,
+            name: "celda".to_string(),
+            mass: 0.0,
+            raw: 0.0,
+            degree: 1,
+            points: Vec::new(),
+            result: None,
+            message: String::new(),
+        }
+    }
+}
+
+// Puertos serie del sistema, más el del simulador si está corriendo
+fn scan_ports() -> Vec<String> {
+    let mut ports: Vec<String> = serialport::available_ports()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| p.port_name)
+        .collect();
+    ports.extend(simulator::available_port());
+    ports
+}
+
 #[derive(PartialEq)]
 enum AppMode {
     Configuration,
     LiveMonitoring,
     CsvViewer,
+    Comparison,
 }
 
 impl App {
     fn new() -> Self {
-        // Escanear puertos disponibles
-        let available_ports = serialport::available_ports()
-            .unwrap_or_default()
-            .into_iter()
-            .map(|p| p.port_name)
-            .collect();
+        let available_ports = scan_ports();
+
+        let calibration_path = "calibraciones.toml".to_string();
+        let (calibrations, error_message) = match CalibrationStore::load(&calibration_path) {
+            Ok(store) => (store, String::new()),
+            Err(e) => (CalibrationStore::default(), e),
+        };
 
         Self {
             last_data: Arc::new(Mutex::new("Esperando datos...".to_string())),
             running: Arc::new(Mutex::new(true)),
             start_time: Instant::now(),
-            data_points: Arc::new(Mutex::new(Vec::new())),
-            port_name: "COM9".to_string(),
-            baud_rate: 115200,
-            file_path: "datos.csv".to_string(),
+            data_points: Arc::new(Mutex::new(History::default())),
+            frame_stats: Arc::new(Mutex::new(FrameStats::default())),
+            source_status: Arc::new(Mutex::new(SourceStatus::default())),
+            clock_stats: Arc::new(Mutex::new(None)),
+            schema: Schema::default(),
+            schema_path: "esquema.toml".to_string(),
+            calibration_path,
+            calibrations,
+            active_calibration: String::new(),
+            raw_thrust: Arc::new(Mutex::new(Vec::new())),
+            wizard: CalibrationWizard::default(),
+            tare_seconds: 2.0,
+            tare_request: Arc::new(Mutex::new(None)),
+            tare_offset: Arc::new(Mutex::new(0.0)),
+            source: SourceConfig::default(),
+            wire_format: WireFormat::Ascii,
+            file_path: String::new(),
+            sessions_root: "ensayos".to_string(),
+            session_form: SessionInfo::default(),
+            session: None,
+            sessions: Vec::new(),
+            markers: Arc::new(Mutex::new(Vec::new())),
+            marker_text: String::new(),
             available_ports,
             configured: false,
             serial_thread: None,
@@ -77,27 +273,27 @@ impl App {
             logo_path: "assets/logo.png".to_string(),
             csv_file_path: "datos.csv".to_string(),
             csv_data_loaded: false,
+            csv_metadata: Metadata::default(),
+            csv_sessions: 0,
             total_impulse: 0.0,
+            propellant_mass: 0.0,
+            burn_thresholds: BurnThresholds::default(),
+            burn: None,
+            motor_delay: String::new(),
+            motor_export: MotorExport::default(),
+            reference_path: "referencia.eng".to_string(),
+            reference: None,
+            reference_tracker: ReferenceTracker::default(),
+            live_window: Some(30.0),
+            comparison_paths: String::new(),
+            comparison: Vec::new(),
             current_mode: AppMode::Configuration,
             show_csv_panel: false,
             show_serial_panel: false,
-            error_message: String::new(),
-        }
-    }
-
-    fn parse_time_to_seconds(time_str: &str) -> Option<f64> {
-        let parts: Vec<&str> = time_str.split(':').collect();
-        if parts.len() == 4 {
-            if let (Ok(hours), Ok(minutes), Ok(seconds), Ok(millis)) = (
-                parts[0].parse::<f64>(),
-                parts[1].parse::<f64>(),
-                parts[2].parse::<f64>(),
-                parts[3].parse::<f64>(),
-            ) {
-                return Some(hours * 3600.0 + minutes * 60.0 + seconds + millis / 1000.0);
-            }
+            show_comparison_panel: false,
+            show_sessions_panel: false,
+            error_message,
         }
-        None
     }
 
     // Nueva función para formatear el tiempo transcurrido
@@ -111,172 +307,453 @@ impl App {
         format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
     }
 
+    fn load_schema(&mut self) -> Result<(), String> {
+        self.schema = Schema::load(&self.schema_path)?;
+        Ok(())
+    }
+
     fn load_csv_data(&mut self) -> Result<(), String> {
-        let file = File::open(&self.csv_file_path)
-            .map_err(|e| format!("Error al abrir el archivo: {}", e))?;
-
-        let reader = BufReader::new(file);
-        let mut data_points = Vec::new();
-        let mut first_line = true;
-
-        for line in reader.lines() {
-            let line = line.map_err(|e| format!("Error al leer línea: {}", e))?;
-
-            if first_line {
-                first_line = false;
-                if line.contains("Tiempo") || line.contains("Empuje") {
-                    continue;
-                }
-            }
-
-            let parts: Vec<&str> = line.split(',').collect();
-            if parts.len() >= 4 {
-                if let (Some(time), Ok(thrust), Ok(temp_ambient), Ok(temp_nozzle)) = (
-                    Self::parse_time_to_seconds(parts[0].trim()),
-                    parts[1].trim().parse::<f64>(),
-                    parts[2].trim().parse::<f64>(),
-                    parts[3].trim().parse::<f64>(),
-                ) {
-                    data_points.push(DataPoint {
-                        time,
-                        thrust,
-                        temp_ambient,
-                        temp_nozzle,
-                    });
-                }
-            }
+        let dataset = dataset::load(&self.schema, &self.csv_file_path)?;
+
+        self.total_impulse = self.calculate_total_impulse(&dataset.data_points);
+        self.burn = self.detect_burn(&dataset.data_points);
+        // Si el registro no trae la masa, se ingresa en el panel de análisis
+        self.propellant_mass = dataset.propellant_mass.unwrap_or(0.0);
+        if let Some(header) = dataset.engine {
+            self.motor_delay = header.delays.clone();
+            self.motor_export.header = header;
         }
+        self.motor_export.masses = dataset.masses;
+        *self.data_points.lock().unwrap() = History::from(dataset.data_points);
+        self.reference_tracker = ReferenceTracker::default();
+        self.csv_metadata = dataset.metadata;
+        self.csv_sessions = dataset.sessions;
+        self.csv_data_loaded = true;
 
-        if data_points.is_empty() {
-            return Err("No se encontraron datos válidos en el archivo CSV".to_string());
-        }
+        Ok(())
+    }
 
-        self.total_impulse = self.calculate_total_impulse(&data_points);
-        *self.data_points.lock().unwrap() = data_points;
-        self.csv_data_loaded = true;
+    fn load_comparison(&mut self) -> Result<(), String> {
+        let mut runs = Vec::new();
+        for path in self
+            .comparison_paths
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+        {
+            let dataset =
+                dataset::load(&self.schema, path).map_err(|e| format!("{}: {}", path, e))?;
+            let data_points = dataset.data_points;
+            runs.push(TestRun {
+                name: Path::new(path)
+                    .file_name()
+                    .map_or(path.to_string(), |n| n.to_string_lossy().to_string()),
+                burn: self.detect_burn(&data_points),
+                data_points,
+                propellant_mass: dataset.propellant_mass.unwrap_or(self.propellant_mass),
+            });
+        }
 
+        if runs.len() < 2 {
+            return Err("Ingrese al menos dos archivos para comparar".to_string());
+        }
+        self.comparison = runs;
         Ok(())
     }
 
-    fn calculate_total_impulse(&self, data_points: &[DataPoint]) -> f64 {
-        if data_points.len() < 2 {
-            return 0.0;
+    fn show_comparison_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Comparación de ensayos");
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Volver a configuración").clicked() {
+                self.current_mode = AppMode::Configuration;
+                self.comparison.clear();
+                self.error_message.clear();
+            }
+        });
+
+        ui.separator();
+
+        let available_rect = ui.available_rect_before_wrap();
+        let graph_width = (available_rect.width() - 20.0) / 2.0;
+        let graph_height = available_rect.height() * 0.55;
+
+        // Curvas alineadas en la ignición de cada ensayo
+        let thrust_index = self.schema.thrust_index();
+        let nozzle_index = self.schema.index(schema::TEMP_NOZZLE);
+        let mut plots = vec![(
+            "Empuje".to_string(),
+            format!("Empuje ({})", self.schema.channels[thrust_index].unit),
+            thrust_index,
+        )];
+        if let Some(i) = nozzle_index {
+            let channel = &self.schema.channels[i];
+            plots.push((
+                channel.name.clone(),
+                format!("{} ({})", channel.name, channel.unit),
+                i,
+            ));
         }
 
-        let mut impulse = 0.0;
-        for i in 1..data_points.len() {
-            let dt = data_points[i].time - data_points[i - 1].time;
-            let avg_thrust = (data_points[i].thrust + data_points[i - 1].thrust) / 2.0;
-            impulse += avg_thrust * dt;
+        ui.columns(plots.len(), |columns| {
+            for (column, (title, y_label, channel)) in columns.iter_mut().zip(&plots) {
+                column.group(|ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.label(RichText::new(title).size(16.0).strong());
+                    });
+                    Plot::new(format!("comparacion_{}", title))
+                        .width(graph_width - 20.0)
+                        .height(graph_height - 40.0)
+                        .x_axis_label("Tiempo desde la ignición (s)")
+                        .y_axis_label(y_label)
+                        .legend(Legend::default())
+                        .show(ui, |plot_ui| {
+                            for (i, run) in self.comparison.iter().enumerate() {
+                                let ignition = run.burn.map_or(0.0, |b| b.ignition_time);
+                                let points: Vec<[f64; 2]> = run
+                                    .data_points
+                                    .iter()
+                                    .map(|dp| [dp.time - ignition, dp.values[*channel]])
+                                    .filter(|p| p[1].is_finite())
+                                    .collect();
+                                plot_ui.line(
+                                    Line::new(PlotPoints::from(points))
+                                        .name(&run.name)
+                                        .color(RUN_COLORS[i % RUN_COLORS.len()]),
+                                );
+                            }
+                        });
+                });
+            }
+        });
+
+        ui.separator();
+
+        // Tabla de repetibilidad
+        let metrics: Vec<[f64; 4]> = self
+            .comparison
+            .iter()
+            .map(|run| {
+                [
+                    run.burn.map_or(f64::NAN, |b| b.impulse),
+                    run.burn.map_or(f64::NAN, |b| b.peak_thrust),
+                    run.burn.map_or(f64::NAN, |b| b.burn_time),
+                    run.isp().unwrap_or(f64::NAN),
+                ]
+            })
+            .collect();
+        let cell = |value: f64, decimals: usize| {
+            if value.is_finite() {
+                format!("{:.*}", decimals, value)
+            } else {
+                "-".to_string()
+            }
+        };
+        const DECIMALS: [usize; 4] = [2, 2, 3, 1];
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("tabla_comparacion")
+                .striped(true)
+                .min_col_width(110.0)
+                .show(ui, |ui| {
+                    for header in [
+                        "Ensayo",
+                        "Impulso (N⋅s)",
+                        "Empuje máx. (N)",
+                        "Combustión (s)",
+                        "Isp (s)",
+                    ] {
+                        ui.label(RichText::new(header).strong());
+                    }
+                    ui.end_row();
+
+                    for (i, (run, values)) in self.comparison.iter().zip(&metrics).enumerate() {
+                        ui.colored_label(RUN_COLORS[i % RUN_COLORS.len()], &run.name);
+                        for (value, decimals) in values.iter().zip(DECIMALS) {
+                            ui.label(cell(*value, decimals));
+                        }
+                        ui.end_row();
+                    }
+
+                    let columns: Vec<Option<(f64, f64)>> = (0..4)
+                        .map(|c| {
+                            let column: Vec<f64> = metrics.iter().map(|m| m[c]).collect();
+                            analysis::mean_std(&column)
+                        })
+                        .collect();
+                    ui.label(RichText::new("Media").strong());
+                    for (stats, decimals) in columns.iter().zip(DECIMALS) {
+                        ui.label(stats.map_or("-".to_string(), |(mean, _)| cell(mean, decimals)));
+                    }
+                    ui.end_row();
+                    ui.label(RichText::new("Desv. estándar").strong());
+                    for (stats, decimals) in columns.iter().zip(DECIMALS) {
+                        ui.label(stats.map_or("-".to_string(), |(mean, std)| {
+                            // Con media nula el porcentaje no tiene sentido
+                            if mean.abs() < f64::EPSILON {
+                                format!("{} (—)", cell(std, decimals))
+                            } else {
+                                format!(
+                                    "{} ({:.1} %)",
+                                    cell(std, decimals),
+                                    std / mean.abs() * 100.0
+                                )
+                            }
+                        }));
+                    }
+                    ui.end_row();
+                });
+        });
+    }
+
+    // Curva de referencia (.eng, .rse o CSV), con el tiempo contado desde
+    // su propia ignición
+    fn load_reference(&mut self) -> Result<(), String> {
+        let path = self.reference_path.trim().to_string();
+        let lower = path.to_lowercase();
+        let mut points = if lower.ends_with(".eng") {
+            engine::read_eng(&path)?.points
+        } else if lower.ends_with(".rse") {
+            engine::read_rse(&path)?.points
+        } else {
+            let thrust_index = self.schema.thrust_index();
+            let data_points = dataset::load(&self.schema, &path)?.data_points;
+            data_points
+                .iter()
+                .map(|dp| [dp.time, dp.values[thrust_index]])
+                .collect()
+        };
+        // Las curvas de motor parten implícitamente en cero
+        if points.first().is_some_and(|p| p[0] > 0.0) {
+            points.insert(0, [0.0, 0.0]);
         }
-        impulse
+
+        let times: Vec<f64> = points.iter().map(|p| p[0]).collect();
+        let thrust: Vec<f64> = points.iter().map(|p| p[1]).collect();
+        let burn = analysis::detect_burn(&times, &thrust, &self.burn_thresholds)
+            .ok_or("La referencia no tiene empuje positivo")?;
+
+        self.reference = Some(ReferenceCurve {
+            name: Path::new(&path)
+                .file_name()
+                .map_or(path.clone(), |n| n.to_string_lossy().to_string()),
+            points: points
+                .iter()
+                .map(|&[t, f]| [t - burn.ignition_time, f])
+                .collect(),
+            peak_thrust: burn.peak_thrust,
+        });
+        self.reference_tracker = ReferenceTracker::default();
+        Ok(())
     }
 
-    fn start_serial_thread(&mut self) {
-        let last_data = Arc::clone(&self.last_data);
-        let running = Arc::clone(&self.running);
-        let data_points = Arc::clone(&self.data_points);
-        let start_time = self.start_time;
-        let port_name = self.port_name.clone();
-        let baud_rate = self.baud_rate;
-        let file_path = self.file_path.clone();
+    // Instante de ignición de los datos en pantalla. En vivo se toma el
+    // primer cruce del umbral respecto del pico de la referencia, porque el
+    // pico propio todavía no se conoce
+    fn measured_ignition(&self) -> Option<f64> {
+        if self.current_mode == AppMode::CsvViewer {
+            self.burn.map(|b| b.ignition_time)
+        } else {
+            self.reference_tracker.live_ignition
+        }
+    }
 
-        let thread = thread::spawn(move || {
-            let port = match serialport::new(&port_name, baud_rate)
-                .timeout(Duration::from_millis(100))
-                .open()
-            {
-                Ok(port) => port,
-                Err(e) => {
-                    let mut data = last_data.lock().unwrap();
-                    *data = format!("Error al abrir el puerto: {}", e);
-                    return;
-                }
-            };
+    // Avanza la búsqueda de la ignición en vivo y la desviación respecto de
+    // la referencia con las muestras llegadas desde el cuadro anterior
+    fn update_reference_tracker(&mut self) {
+        let reference = match &self.reference {
+            Some(reference) => reference,
+            None => return,
+        };
+        let thrust_index = self.schema.thrust_index();
+        let data_points = self.data_points.lock().unwrap();
+        let tracker = &mut self.reference_tracker;
 
-            let file = match OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&file_path)
-            {
-                Ok(file) => file,
-                Err(e) => {
-                    let mut data = last_data.lock().unwrap();
-                    *data = format!("Error al abrir el archivo: {}", e);
-                    return;
-                }
-            };
+        // Una sesión nueva o un archivo recargado empiezan de cero
+        if data_points.len() < tracker.compared.max(tracker.ignition_scan) {
+            *tracker = ReferenceTracker::default();
+        }
 
-            let mut file = file;
-            let mut port = port;
+        // En vivo la ignición es el primer cruce del umbral respecto del pico
+        // de la referencia, porque el pico propio todavía no se conoce
+        if self.current_mode != AppMode::CsvViewer && tracker.live_ignition.is_none() {
+            let level = reference.peak_thrust * self.burn_thresholds.ignition / 100.0;
+            tracker.live_ignition = data_points[tracker.ignition_scan..]
+                .iter()
+                .find(|dp| dp.values[thrust_index] >= level)
+                .map(|dp| dp.time);
+            tracker.ignition_scan = data_points.len();
+        }
 
-            if file.metadata().unwrap().len() == 0 {
-                let _ = writeln!(
-                    file,
-                    "Tiempo,Empuje,Temperatura Ambiente,Temperatura Tobera"
+        let ignition = if self.current_mode == AppMode::CsvViewer {
+            self.burn.map(|b| b.ignition_time)
+        } else {
+            tracker.live_ignition
+        };
+        if tracker.ignition != ignition {
+            tracker.ignition = ignition;
+            tracker.compared = 0;
+            tracker.deviation = analysis::RunningDeviation::default();
+        }
+        if let Some(ignition) = ignition {
+            for dp in &data_points[tracker.compared..] {
+                tracker.deviation.push(
+                    &reference.points,
+                    dp.time - ignition,
+                    dp.values[thrust_index],
                 );
             }
+            tracker.compared = data_points.len();
+        }
+    }
 
-            let mut data = last_data.lock().unwrap();
-            *data = "Conexión exitosa, esperando datos...".to_string();
-            drop(data);
+    fn reference_deviation(&self) -> Option<analysis::Deviation> {
+        let reference = self.reference.as_ref()?;
+        self.reference_tracker.ignition?;
+        self.reference_tracker.deviation.result(&reference.points)
+    }
 
-            loop {
-                if !*running.lock().unwrap() {
-                    break;
-                }
+    // Curva de referencia en las coordenadas del gráfico: en el visor se
+    // desplaza a la ignición medida; en vivo se evalúa en las muestras
+    // dibujadas
+    fn reference_overlay(
+        &self,
+        data_points: &[DataPoint],
+        indices: &[usize],
+    ) -> Option<Vec<[f64; 2]>> {
+        let reference = self.reference.as_ref()?;
+        let ignition = self.measured_ignition()?;
+        if self.current_mode == AppMode::CsvViewer {
+            Some(
+                reference
+                    .points
+                    .iter()
+                    .map(|&[t, f]| [t + ignition, f])
+                    .collect(),
+            )
+        } else {
+            Some(
+                indices
+                    .iter()
+                    .filter_map(|&i| {
+                        analysis::interpolate_curve(
+                            &reference.points,
+                            data_points[i].time - ignition,
+                        )
+                        .map(|f| [data_points[i].time, f])
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    fn calculate_total_impulse(&self, data_points: &[DataPoint]) -> f64 {
+        let thrust = dataset::channel(data_points, self.schema.thrust_index());
+        analysis::impulse(&dataset::times(data_points), &thrust)
+    }
 
-                let mut buf = [0; 64];
-                if let Ok(n) = port.read(&mut buf) {
-                    if n > 0 {
-                        let received = String::from_utf8_lossy(&buf[..n]).trim().to_string();
-                        let parts: Vec<&str> = received.split(',').collect();
+    fn detect_burn(&self, data_points: &[DataPoint]) -> Option<BurnMetrics> {
+        let thrust = dataset::channel(data_points, self.schema.thrust_index());
+        analysis::detect_burn(&dataset::times(data_points), &thrust, &self.burn_thresholds)
+    }
 
-                        if parts.len() == 3 {
-                            let elapsed = start_time.elapsed();
-                            let timestamp = format!(
-                                "{:02}:{:02}:{:02}:{:03}",
-                                elapsed.as_secs() / 3600,
-                                (elapsed.as_secs() % 3600) / 60,
-                                elapsed.as_secs() % 60,
-                                elapsed.subsec_millis()
-                            );
+    fn motor_impulse(&self) -> f64 {
+        analysis::motor_impulse(self.burn, self.total_impulse)
+    }
 
-                            if let (Ok(thrust), Ok(temp_ambient), Ok(temp_nozzle)) = (
-                                parts[0].trim().parse::<f64>(),
-                                parts[1].trim().parse::<f64>(),
-                                parts[2].trim().parse::<f64>(),
-                            ) {
-                                let _ = writeln!(
-                                    file,
-                                    "{},{},{},{}",
-                                    timestamp, thrust, temp_ambient, temp_nozzle
-                                );
-                                let mut data = last_data.lock().unwrap();
-                                *data = format!("{} | {} | {}", thrust, temp_ambient, temp_nozzle);
+    fn motor_designation(&self, data_points: &[DataPoint]) -> Option<(String, MotorClass)> {
+        analysis::motor_designation(
+            self.burn,
+            self.total_impulse,
+            dataset::duration(data_points),
+            &self.motor_delay,
+        )
+    }
 
-                                let mut data_points = data_points.lock().unwrap();
-                                let time_seconds = elapsed.as_secs_f64();
+    fn motor_burn_time(&self, data_points: &[DataPoint]) -> f64 {
+        analysis::motor_burn_time(self.burn, dataset::duration(data_points))
+    }
 
-                                if data_points.len() >= 100 {
-                                    data_points.remove(0);
-                                }
-                                data_points.push(DataPoint {
-                                    time: time_seconds,
-                                    thrust,
-                                    temp_ambient,
-                                    temp_nozzle,
-                                });
-                            }
-                        }
-                    }
-                }
-            }
+    fn start_serial_thread(&mut self) {
+        let config = AcquisitionConfig {
+            source: self.source.clone(),
+            wire_format: self.wire_format,
+            schema: self.schema.clone(),
+            file_path: self.file_path.clone(),
+            calibration: self.calibrations.get(&self.active_calibration).cloned(),
+            propellant_mass: self.propellant_mass,
+            tare_seconds: self.tare_seconds,
+            start_time: self.start_time,
+            operator: self.session_form.operator.clone(),
+            notes: self.session_form.notes.clone(),
+        };
+        let shared = SharedState {
+            last_data: Arc::clone(&self.last_data),
+            running: Arc::clone(&self.running),
+            data_points: Arc::clone(&self.data_points),
+            frame_stats: Arc::clone(&self.frame_stats),
+            source_status: Arc::clone(&self.source_status),
+            clock_stats: Arc::clone(&self.clock_stats),
+            raw_thrust: Arc::clone(&self.raw_thrust),
+            tare_request: Arc::clone(&self.tare_request),
+            tare_offset: Arc::clone(&self.tare_offset),
+            markers: Arc::clone(&self.markers),
+        };
+
+        // El error ya queda en `last_data` para el panel de estado
+        let thread = thread::spawn(move || {
+            let _ = acquisition::run(&config, &shared);
         });
 
         self.serial_thread = Some(thread);
     }
 
+    // Crea la carpeta de la sesión; el registro se escribe dentro de ella
+    fn start_session(&mut self) -> Result<(), String> {
+        let info = SessionInfo {
+            propellant_mass: self.propellant_mass,
+            ..self.session_form.clone()
+        };
+        let session = Session::create(self.sessions_root.trim(), &info)?;
+        self.file_path = session.log_path();
+        self.session = Some(session);
+        Ok(())
+    }
+
+    // Detiene la adquisición y cierra la sesión en curso (eventos, CSV
+    // procesado y resumen)
+    fn stop_acquisition(&mut self) {
+        *self.running.lock().unwrap() = false;
+        if let Some(thread) = self.serial_thread.take() {
+            let _ = thread.join();
+        }
+        if let Some(mut session) = self.session.take() {
+            if let Err(e) = session.finish(&self.schema, self.burn_thresholds) {
+                self.error_message = format!("Error: {}", e);
+            }
+        }
+    }
+
+    // Promedia el empuje de los últimos `tare_seconds` y lo deja pendiente
+    // para que el hilo serial lo reste de las muestras siguientes
+    fn request_tare(&mut self) {
+        let average = match self
+            .data_points
+            .lock()
+            .unwrap()
+            .average(self.schema.thrust_index(), self.tare_seconds)
+        {
+            Some(average) => average,
+            None => return,
+        };
+
+        // Las muestras en pantalla ya tienen restado el offset vigente, así
+        // que el promedio se suma a él; un pedido aún no aplicado se reemplaza
+        *self.tare_request.lock().unwrap() = Some(average);
+    }
+
     fn show_config_window(&mut self, ui: &mut egui::Ui) {
         // Centrar todo el contenido
         ui.vertical_centered(|ui| {
@@ -311,6 +788,8 @@ impl App {
                 {
                     self.show_csv_panel = true;
                     self.show_serial_panel = false;
+                    self.show_comparison_panel = false;
+                    self.show_sessions_panel = false;
                     self.error_message.clear();
                 }
 
@@ -325,8 +804,55 @@ impl App {
                 {
                     self.show_serial_panel = true;
                     self.show_csv_panel = false;
+                    self.show_comparison_panel = false;
+                    self.show_sessions_panel = false;
                     self.error_message.clear();
                 }
+
+                ui.add_space(15.0);
+
+                if ui
+                    .add_sized(
+                        [280.0, 60.0],
+                        egui::Button::new(RichText::new("🧪 Comparar ensayos").size(16.0)),
+                    )
+                    .clicked()
+                {
+                    self.show_comparison_panel = true;
+                    self.show_csv_panel = false;
+                    self.show_serial_panel = false;
+                    self.show_sessions_panel = false;
+                    self.error_message.clear();
+                }
+
+                ui.add_space(15.0);
+
+                if ui
+                    .add_sized(
+                        [280.0, 60.0],
+                        egui::Button::new(RichText::new("🗂️ Ensayos anteriores").size(16.0)),
+                    )
+                    .clicked()
+                {
+                    self.show_sessions_panel = true;
+                    self.show_csv_panel = false;
+                    self.show_serial_panel = false;
+                    self.show_comparison_panel = false;
+                    self.sessions = Session::list(self.sessions_root.trim());
+                    self.error_message.clear();
+                }
+
+                ui.add_space(15.0);
+
+                if ui
+                    .add_sized(
+                        [280.0, 60.0],
+                        egui::Button::new(RichText::new("⚖️ Calibrar celda de carga").size(16.0)),
+                    )
+                    .clicked()
+                {
+                    self.wizard.open = true;
+                }
             });
         });
 
@@ -338,103 +864,514 @@ impl App {
         if self.show_serial_panel {
             self.show_serial_panel_ui(ui);
         }
+
+        if self.show_comparison_panel {
+            self.show_comparison_panel_ui(ui);
+        }
+
+        if self.show_sessions_panel {
+            self.show_sessions_panel_ui(ui);
+        }
     }
 
-    fn show_csv_panel_ui(&mut self, ui: &mut egui::Ui) {
+    // Explorador de sesiones: lista las carpetas de ensayo y abre su
+    // registro en el visor de CSV
+    fn show_sessions_panel_ui(&mut self, ui: &mut egui::Ui) {
         ui.add_space(30.0);
         ui.separator();
         ui.add_space(20.0);
 
         ui.vertical_centered(|ui| {
-            ui.heading("Cargar datos desde CSV");
+            ui.heading("Ensayos anteriores");
             ui.add_space(20.0);
 
             ui.horizontal(|ui| {
-                ui.label("Archivo:");
-                ui.text_edit_singleline(&mut self.csv_file_path);
+                ui.label("Carpeta de ensayos:");
+                ui.text_edit_singleline(&mut self.sessions_root);
+                if ui.button("🔄").clicked() {
+                    self.sessions = Session::list(self.sessions_root.trim());
+                }
             });
 
-            ui.add_space(15.0);
+            ui.add_space(10.0);
 
-            ui.horizontal(|ui| {
-                if ui
-                    .add_sized([120.0, 35.0], egui::Button::new("Cargar"))
-                    .clicked()
-                {
-                    match self.load_csv_data() {
-                        Ok(()) => {
-                            self.current_mode = AppMode::CsvViewer;
-                            self.show_csv_panel = false;
-                            self.error_message.clear();
-                        }
-                        Err(e) => {
-                            self.error_message = format!("Error: {}", e);
+            if self.sessions.is_empty() {
+                ui.label("No hay sesiones en la carpeta");
+            }
+
+            let mut selected = None;
+            let mut reprocess = None;
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("tabla_sesiones")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for label in ["Sesión", "Motor", "Operador", "Masa", "Notas", "", ""] {
+                                ui.label(RichText::new(label).strong());
+                            }
+                            ui.end_row();
+
+                            for (i, session) in self.sessions.iter().enumerate() {
+                                let info = &session.info;
+                                ui.label(session.name());
+                                ui.label(&info.motor);
+                                ui.label(&info.operator);
+                                if info.propellant_mass > 0.0 {
+                                    ui.label(format!("{:.3} kg", info.propellant_mass));
+                                } else {
+                                    ui.label("-");
+                                }
+                                ui.label(info.notes.lines().next().unwrap_or_default());
+                                if ui.button("Abrir").clicked() {
+                                    selected = Some(i);
+                                }
+                                // Una sesión reprocesada no tiene captura propia
+                                let captured = std::path::Path::new(&capture::capture_path(
+                                    &session.log_path(),
+                                ))
+                                .exists();
+                                if captured && ui.button("Reprocesar").clicked() {
+                                    reprocess = Some(i);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            if let Some(i) = selected {
+                self.csv_file_path = self.sessions[i].log_path();
+                match self.load_schema().and_then(|()| self.load_csv_data()) {
+                    Ok(()) => {
+                        // El registro puede no traer la masa; la sesión sí
+                        if self.propellant_mass == 0.0 {
+                            self.propellant_mass = self.sessions[i].info.propellant_mass;
                         }
+                        self.current_mode = AppMode::CsvViewer;
+                        self.show_sessions_panel = false;
+                        self.error_message.clear();
+                    }
+                    Err(e) => {
+                        self.error_message = format!("Error: {}", e);
                     }
                 }
+            }
 
-                if ui
-                    .add_sized([120.0, 35.0], egui::Button::new("Cancelar"))
-                    .clicked()
-                {
-                    self.show_csv_panel = false;
-                    self.error_message.clear();
+            // Reprocesar es adquirir de nuevo con la captura cruda como
+            // fuente: queda una sesión nueva y la original no se toca
+            if let Some(i) = reprocess {
+                let session = &self.sessions[i];
+                let capture_path = capture::capture_path(&session.log_path());
+                match capture::read(&capture_path) {
+                    Ok(captured) => {
+                        self.source.kind = SourceKind::Capture;
+                        self.source.capture_path = capture_path;
+                        self.source.capture_session = 0;
+                        self.session_form = SessionInfo {
+                            notes: format!("Reprocesado de {}", session.name()),
+                            ..session.info.clone()
+                        };
+                        self.propellant_mass = session.info.propellant_mass;
+                        self.show_sessions_panel = false;
+                        self.show_serial_panel = true;
+                        self.error_message.clear();
+                        if let Some(last) = captured.last() {
+                            self.wire_format = last.wire_format;
+                            // Se reprocesa con la calibración del ensayo, no
+                            // con la que haya quedado elegida
+                            let recorded = dataset::read_header(&last.header).0.calibration;
+                            match recorded.as_deref().map(calibration::profile_name) {
+                                Some(None) => self.active_calibration.clear(),
+                                Some(Some(name)) if self.calibrations.get(name).is_some() => {
+                                    self.active_calibration = name.to_string();
+                                }
+                                Some(Some(name)) => {
+                                    self.active_calibration.clear();
+                                    self.error_message = format!(
+                                        "El ensayo se registró con la calibración \"{}\", que no \
+                                         existe; elija un perfil antes de Iniciar",
+                                        name
+                                    );
+                                }
+                                None => {
+                                    self.error_message = "La captura no indica la calibración \
+                                                          del ensayo; revise el perfil elegido"
+                                        .to_string();
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        self.error_message = format!("Error: {}", e);
+                    }
                 }
-            });
+            }
+
+            ui.add_space(15.0);
+
+            if ui
+                .add_sized([120.0, 35.0], egui::Button::new("Cancelar"))
+                .clicked()
+            {
+                self.show_sessions_panel = false;
+                self.error_message.clear();
+            }
         });
     }
 
-    fn show_serial_panel_ui(&mut self, ui: &mut egui::Ui) {
+    fn show_comparison_panel_ui(&mut self, ui: &mut egui::Ui) {
         ui.add_space(30.0);
         ui.separator();
         ui.add_space(20.0);
 
         ui.vertical_centered(|ui| {
-            ui.heading("Configuración Serial");
+            ui.heading("Comparar ensayos");
+            ui.add_space(20.0);
+
+            ui.label("Archivos CSV (uno por línea):");
+            ui.add(
+                egui::TextEdit::multiline(&mut self.comparison_paths)
+                    .desired_rows(5)
+                    .desired_width(400.0),
+            );
+
+            ui.add_space(10.0);
+
+            ui.horizontal(|ui| {
+                ui.label("Esquema:");
+                ui.text_edit_singleline(&mut self.schema_path);
+            });
+
+            ui.add_space(10.0);
+
+            // Para los registros que no anotan la masa
+            ui.horizontal(|ui| {
+                ui.label("Masa de propelente:");
+                ui.add(
+                    egui::DragValue::new(&mut self.propellant_mass)
+                        .clamp_range(0.0..=1000.0)
+                        .speed(0.001)
+                        .suffix(" kg"),
+                );
+            });
+
+            ui.add_space(15.0);
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_sized([120.0, 35.0], egui::Button::new("Comparar"))
+                    .clicked()
+                {
+                    match self.load_schema().and_then(|()| self.load_comparison()) {
+                        Ok(()) => {
+                            self.current_mode = AppMode::Comparison;
+                            self.show_comparison_panel = false;
+                            self.error_message.clear();
+                        }
+                        Err(e) => {
+                            self.error_message = format!("Error: {}", e);
+                        }
+                    }
+                }
+
+                if ui
+                    .add_sized([120.0, 35.0], egui::Button::new("Cancelar"))
+                    .clicked()
+                {
+                    self.show_comparison_panel = false;
+                    self.error_message.clear();
+                }
+            });
+        });
+    }
+
+    fn show_csv_panel_ui(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(30.0);
+        ui.separator();
+        ui.add_space(20.0);
+
+        ui.vertical_centered(|ui| {
+            ui.heading("Cargar datos desde CSV");
+            ui.add_space(20.0);
+
+            ui.horizontal(|ui| {
+                ui.label("Archivo:");
+                ui.text_edit_singleline(&mut self.csv_file_path);
+            });
+
+            ui.add_space(10.0);
+
+            ui.horizontal(|ui| {
+                ui.label("Esquema:");
+                ui.text_edit_singleline(&mut self.schema_path);
+            });
+
+            ui.add_space(15.0);
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_sized([120.0, 35.0], egui::Button::new("Cargar"))
+                    .clicked()
+                {
+                    match self.load_schema().and_then(|()| self.load_csv_data()) {
+                        Ok(()) => {
+                            self.current_mode = AppMode::CsvViewer;
+                            self.show_csv_panel = false;
+                            self.error_message.clear();
+                        }
+                        Err(e) => {
+                            self.error_message = format!("Error: {}", e);
+                        }
+                    }
+                }
+
+                if ui
+                    .add_sized([120.0, 35.0], egui::Button::new("Cancelar"))
+                    .clicked()
+                {
+                    self.show_csv_panel = false;
+                    self.error_message.clear();
+                }
+            });
+        });
+    }
+
+    // Campos propios de la fuente elegida
+    fn show_source_settings(&mut self, ui: &mut egui::Ui) {
+        let source = &mut self.source;
+        match source.kind {
+            SourceKind::Serial => {
+                ui.horizontal(|ui| {
+                    ui.label("Puerto:");
+                    egui::ComboBox::from_id_source("puerto")
+                        .selected_text(&source.port_name)
+                        .show_ui(ui, |ui| {
+                            for port in &self.available_ports {
+                                ui.selectable_value(&mut source.port_name, port.clone(), port);
+                            }
+                        });
+
+                    if ui.button("🔄").clicked() {
+                        self.available_ports = scan_ports();
+                    }
+                });
+
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Velocidad:");
+                    egui::ComboBox::from_id_source("baud")
+                        .selected_text(source.baud_rate.to_string())
+                        .show_ui(ui, |ui| {
+                            for &rate in &[9600, 19200, 38400, 57600, 115200, 230400] {
+                                ui.selectable_value(&mut source.baud_rate, rate, rate.to_string());
+                            }
+                        });
+                });
+            }
+            SourceKind::Tcp => {
+                ui.horizontal(|ui| {
+                    ui.label("Controlador (host:puerto):");
+                    ui.text_edit_singleline(&mut source.tcp_address);
+                });
+            }
+            SourceKind::TcpServer => {
+                ui.horizontal(|ui| {
+                    ui.label("Escuchar en:");
+                    ui.text_edit_singleline(&mut source.tcp_listen);
+                });
+            }
+            SourceKind::Udp => {
+                ui.horizontal(|ui| {
+                    ui.label("Escuchar en:");
+                    ui.text_edit_singleline(&mut source.udp_address);
+                });
+            }
+            SourceKind::Capture => {
+                ui.horizontal(|ui| {
+                    ui.label("Captura:");
+                    ui.text_edit_singleline(&mut source.capture_path);
+                });
+
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Sesión:");
+                    ui.add(egui::DragValue::new(&mut source.capture_session).clamp_range(0..=999));
+                    ui.label("(0 = la última)");
+                    ui.label("Velocidad:");
+                    ui.add(
+                        egui::DragValue::new(&mut source.speed)
+                            .clamp_range(0.1..=100.0)
+                            .speed(0.1)
+                            .prefix("x"),
+                    );
+                });
+            }
+            SourceKind::Replay | SourceKind::Synthetic => {
+                if source.kind == SourceKind::Replay {
+                    ui.horizontal(|ui| {
+                        ui.label("Registro:");
+                        ui.text_edit_singleline(&mut source.replay_path);
+                    });
+                } else {
+                    let curve = &mut source.synthetic;
+                    ui.horizontal(|ui| {
+                        ui.label("Empuje máximo:");
+                        ui.add(
+                            egui::DragValue::new(&mut curve.peak_thrust)
+                                .clamp_range(1.0..=100000.0)
+                                .suffix(" N"),
+                        );
+                        ui.label("Combustión:");
+                        ui.add(
+                            egui::DragValue::new(&mut curve.burn_time)
+                                .clamp_range(0.1..=120.0)
+                                .speed(0.1)
+                                .suffix(" s"),
+                        );
+                        ui.label("Frecuencia:");
+                        ui.add(
+                            egui::DragValue::new(&mut curve.rate)
+                                .clamp_range(1.0..=2000.0)
+                                .suffix(" Hz"),
+                        );
+                    });
+                }
+
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Velocidad:");
+                    ui.add(
+                        egui::DragValue::new(&mut source.speed)
+                            .clamp_range(0.1..=100.0)
+                            .speed(0.1)
+                            .prefix("x"),
+                    );
+                    ui.checkbox(&mut source.repeat, "Repetir");
+                });
+            }
+        }
+
+        ui.add_space(10.0);
+    }
+
+    fn show_serial_panel_ui(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(30.0);
+        ui.separator();
+        ui.add_space(20.0);
+
+        ui.vertical_centered(|ui| {
+            ui.heading("Configuración de Adquisición");
             ui.add_space(20.0);
 
             ui.set_max_width(400.0);
 
-            // Puerto
+            // De dónde llegan los datos
             ui.horizontal(|ui| {
-                ui.label("Puerto:");
-                egui::ComboBox::from_id_source("puerto")
-                    .selected_text(&self.port_name)
+                ui.label("Fuente:");
+                egui::ComboBox::from_id_source("fuente")
+                    .selected_text(self.source.kind.label())
                     .show_ui(ui, |ui| {
-                        for port in &self.available_ports {
-                            ui.selectable_value(&mut self.port_name, port.clone(), port);
+                        for kind in SourceKind::ALL {
+                            ui.selectable_value(&mut self.source.kind, kind, kind.label());
                         }
                     });
+            });
 
-                if ui.button("🔄").clicked() {
-                    self.available_ports = serialport::available_ports()
-                        .unwrap_or_default()
-                        .into_iter()
-                        .map(|p| p.port_name)
-                        .collect();
-                }
+            ui.add_space(10.0);
+
+            self.show_source_settings(ui);
+
+            // Formato de los datos en el cable
+            ui.horizontal(|ui| {
+                ui.label("Formato:");
+                egui::ComboBox::from_id_source("formato")
+                    .selected_text(self.wire_format.label())
+                    .show_ui(ui, |ui| {
+                        for format in [WireFormat::Ascii, WireFormat::Binary] {
+                            ui.selectable_value(&mut self.wire_format, format, format.label());
+                        }
+                    });
             });
 
             ui.add_space(10.0);
 
-            // Baud rate
+            // Cada ensayo queda en su propia carpeta dentro de esta
             ui.horizontal(|ui| {
-                ui.label("Velocidad:");
-                egui::ComboBox::from_id_source("baud")
-                    .selected_text(self.baud_rate.to_string())
+                ui.label("Carpeta de ensayos:");
+                ui.text_edit_singleline(&mut self.sessions_root);
+            });
+
+            ui.add_space(10.0);
+
+            // Canales que envía la placa
+            ui.horizontal(|ui| {
+                ui.label("Esquema:");
+                ui.text_edit_singleline(&mut self.schema_path);
+            });
+
+            ui.add_space(10.0);
+
+            // Perfil que convierte las cuentas crudas de la celda en newtons
+            ui.horizontal(|ui| {
+                ui.label("Calibración:");
+                let selected = if self.active_calibration.is_empty() {
+                    "Sin calibrar".to_string()
+                } else {
+                    self.active_calibration.clone()
+                };
+                egui::ComboBox::from_id_source("calibracion")
+                    .selected_text(selected)
                     .show_ui(ui, |ui| {
-                        for &rate in &[9600, 19200, 38400, 57600, 115200, 230400] {
-                            ui.selectable_value(&mut self.baud_rate, rate, rate.to_string());
+                        ui.selectable_value(
+                            &mut self.active_calibration,
+                            String::new(),
+                            "Sin calibrar",
+                        );
+                        for profile in &self.calibrations.profiles {
+                            ui.selectable_value(
+                                &mut self.active_calibration,
+                                profile.name.clone(),
+                                &profile.name,
+                            );
                         }
                     });
             });
 
             ui.add_space(10.0);
 
-            // Archivo
+            // Se anota en el registro para calcular el Isp al analizarlo
             ui.horizontal(|ui| {
-                ui.label("Archivo:");
-                ui.text_edit_singleline(&mut self.file_path);
+                ui.label("Masa de propelente:");
+                ui.add(
+                    egui::DragValue::new(&mut self.propellant_mass)
+                        .clamp_range(0.0..=1000.0)
+                        .speed(0.001)
+                        .suffix(" kg"),
+                );
+            });
+
+            ui.add_space(10.0);
+
+            // Datos del ensayo para los metadatos de la sesión; operador y
+            // notas también quedan en la cabecera del registro
+            let form = &mut self.session_form;
+            for (label, value) in [
+                ("Motor:", &mut form.motor),
+                ("Lote de propelente:", &mut form.propellant_batch),
+                ("Operador:", &mut form.operator),
+                ("Clima:", &mut form.weather),
+            ] {
+                ui.horizontal(|ui| {
+                    ui.label(label);
+                    ui.text_edit_singleline(value);
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.label("Notas:");
+                ui.add(egui::TextEdit::multiline(&mut form.notes).desired_rows(2));
             });
 
             ui.add_space(20.0);
@@ -444,12 +1381,20 @@ impl App {
                     .add_sized([120.0, 35.0], egui::Button::new("Iniciar"))
                     .clicked()
                 {
-                    if !self.port_name.is_empty() && !self.file_path.is_empty() {
-                        self.current_mode = AppMode::LiveMonitoring;
-                        self.show_serial_panel = false;
-                        self.start_time = Instant::now(); // Reiniciar el tiempo cuando se inicia el monitoreo
-                        self.start_serial_thread();
-                        self.error_message.clear();
+                    if self.source.is_complete() && !self.sessions_root.trim().is_empty() {
+                        match self.load_schema().and_then(|()| self.start_session()) {
+                            Ok(()) => {
+                                self.current_mode = AppMode::LiveMonitoring;
+                                self.show_serial_panel = false;
+                                self.start_time = Instant::now(); // Reiniciar el tiempo cuando se inicia el monitoreo
+                                self.reference_tracker = ReferenceTracker::default();
+                                self.start_serial_thread();
+                                self.error_message.clear();
+                            }
+                            Err(e) => {
+                                self.error_message = format!("Error: {}", e);
+                            }
+                        }
                     } else {
                         self.error_message = "Por favor, complete todos los campos".to_string();
                     }
@@ -504,6 +1449,46 @@ impl App {
                 ui.label(format!("Archivo: {}", self.csv_file_path));
                 ui.label(format!("Impulso total: {:.2} N⋅s", self.total_impulse));
             });
+
+            // Un registro continuado no mezcla ensayos: se muestra el último
+            if self.csv_sessions > 1 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!(
+                        "El archivo tiene {} sesiones; se usa la última",
+                        self.csv_sessions
+                    ),
+                );
+            }
+
+            // Cabecera del registro, si la tiene
+            let metadata = &self.csv_metadata;
+            let mut details = Vec::new();
+            if let Some(started_at) = metadata.started_at {
+                details.push(format!(
+                    "Inicio: {}",
+                    started_at
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M:%S")
+                ));
+            }
+            for (label, value) in [
+                ("Equipo", &metadata.host),
+                ("Puerto", &metadata.port),
+                ("Versión", &metadata.version),
+                ("Calibración", &metadata.calibration),
+                ("Operador", &metadata.operator),
+            ] {
+                if let Some(value) = value {
+                    details.push(format!("{}: {}", label, value));
+                }
+            }
+            if !details.is_empty() {
+                ui.label(details.join(" | "));
+            }
+            if let Some(notes) = &metadata.notes {
+                ui.label(format!("Notas: {}", notes));
+            }
         } else {
             let data = self.last_data.lock().unwrap();
             ui.label(format!("Últimos datos: {}", *data));
@@ -512,16 +1497,56 @@ impl App {
         ui.horizontal(|ui| {
             if !is_csv_mode {
                 if ui.button("Detener").clicked() {
-                    *self.running.lock().unwrap() = false;
+                    self.stop_acquisition();
+                }
+
+                if ui.button("⚖️ Calibrar").clicked() {
+                    self.wizard.open = true;
+                }
+
+                ui.separator();
+                ui.add(
+                    egui::DragValue::new(&mut self.tare_seconds)
+                        .clamp_range(0.1..=30.0)
+                        .speed(0.1)
+                        .suffix(" s"),
+                );
+                if ui.button("Tara").clicked() {
+                    self.request_tare();
+                }
+                ui.label(format!(
+                    "Offset: {:.2} N",
+                    *self.tare_offset.lock().unwrap()
+                ));
+
+                // Las marcas quedan como eventos en el registro de la sesión
+                ui.separator();
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.marker_text)
+                        .hint_text("Evento")
+                        .desired_width(120.0),
+                );
+                if ui.button("📍 Marcar").clicked() {
+                    let text = match self.marker_text.trim() {
+                        "" => "marca".to_string(),
+                        text => text.to_string(),
+                    };
+                    self.markers.lock().unwrap().push(text);
+                    self.marker_text.clear();
+                }
+
+                ui.separator();
+                ui.label("Ventana:");
+                for (label, window) in LIVE_WINDOWS {
+                    ui.selectable_value(&mut self.live_window, window, label);
                 }
             }
 
             if ui.button("Volver a configuración").clicked() {
+                // Un error al cerrar la sesión queda visible en la configuración
+                self.error_message.clear();
                 if !is_csv_mode {
-                    *self.running.lock().unwrap() = false;
-                    if let Some(thread) = self.serial_thread.take() {
-                        let _ = thread.join();
-                    }
+                    self.stop_acquisition();
                     *self.running.lock().unwrap() = true;
                 }
 
@@ -532,117 +1557,140 @@ impl App {
                 self.show_serial_panel = false;
                 self.data_points.lock().unwrap().clear();
                 self.total_impulse = 0.0;
-                self.error_message.clear();
+                self.burn = None;
                 return;
             }
 
-            if is_csv_mode {
-                if ui.button("Exportar resumen").clicked() {
-                    self.export_summary();
+            if is_csv_mode && ui.button("Exportar resumen").clicked() {
+                self.export_summary();
+            }
+
+            if is_csv_mode && ui.button("Exportar motor").clicked() {
+                // El formulario parte con los datos ya conocidos del motor
+                let data_points = self.data_points.lock().unwrap();
+                if let Some((designation, _)) = self.motor_designation(&data_points) {
+                    self.motor_export.header.name = designation;
+                }
+                drop(data_points);
+                if !self.motor_delay.trim().is_empty() {
+                    self.motor_export.header.delays = self.motor_delay.trim().to_string();
+                }
+                self.motor_export.header.propellant_mass = self.propellant_mass;
+                self.motor_export.message.clear();
+                self.motor_export.open = true;
+            }
+        });
+
+        self.update_reference_tracker();
+
+        ui.horizontal(|ui| {
+            ui.label("📐 Referencia:");
+            ui.text_edit_singleline(&mut self.reference_path);
+            if ui.button("Cargar").clicked() {
+                match self.load_reference() {
+                    Ok(()) => self.error_message.clear(),
+                    Err(e) => self.error_message = format!("Error: {}", e),
+                }
+            }
+            if self.reference.is_some() && ui.button("Quitar").clicked() {
+                self.reference = None;
+                self.reference_tracker = ReferenceTracker::default();
+            }
+
+            if let Some(reference) = &self.reference {
+                match self.reference_deviation() {
+                    Some(deviation) => {
+                        ui.label(format!(
+                            "{} | RMSE: {:.2} N | Δ pico: {:+.2} N ({:+.1} %) | Δ impulso: {:+.1} %",
+                            reference.name,
+                            deviation.rmse,
+                            deviation.peak_difference,
+                            deviation.peak_difference_pct,
+                            deviation.impulse_difference_pct
+                        ));
+                    }
+                    None => {
+                        ui.label(format!("{} | esperando la ignición", reference.name));
+                    }
                 }
             }
         });
 
+        if !self.error_message.is_empty() {
+            ui.colored_label(egui::Color32::RED, &self.error_message);
+        }
+
         ui.separator();
 
         let data_points = self.data_points.lock().unwrap();
-        let x_vals: Vec<f64> = if is_csv_mode {
-            data_points.iter().map(|dp| dp.time).collect()
-        } else {
-            (0..data_points.len()).map(|i| i as f64).collect()
+        // En vivo la ventana avanza con la última muestra; si no, se dibuja
+        // toda la sesión
+        let window = match (data_points.last(), self.live_window) {
+            (Some(last), Some(seconds)) if !is_csv_mode => Some([last.time - seconds, last.time]),
+            _ => None,
+        };
+        let visible = match window {
+            Some([start, end]) => data_points.range(start, end),
+            None => 0..data_points.len(),
         };
-
-        let thrust_vals: Vec<f64> = data_points.iter().map(|dp| dp.thrust).collect();
-        let temp_ambient_vals: Vec<f64> = data_points.iter().map(|dp| dp.temp_ambient).collect();
-        let temp_nozzle_vals: Vec<f64> = data_points.iter().map(|dp| dp.temp_nozzle).collect();
 
         let available_rect = ui.available_rect_before_wrap();
         let graph_width = (available_rect.width() - 20.0) / 2.0;
         let graph_height = (available_rect.height() - 100.0) / 2.0;
 
-        ui.columns(2, |columns| {
-            columns[0].group(|ui| {
-                ui.set_min_size(egui::vec2(graph_width, graph_height * 2.0 + 20.0));
-                ui.vertical_centered(|ui| {
-                    ui.label(RichText::new("Empuje").size(18.0).strong());
-                });
-
-                Plot::new("thrust_plot")
-                    .width(graph_width - 20.0)
-                    .height(graph_height * 2.0 - 20.0)
-                    .x_axis_label(if is_csv_mode {
-                        "Tiempo (s)"
-                    } else {
-                        "Muestras"
-                    })
-                    .y_axis_label("Empuje (N)")
-                    .show(ui, |plot_ui| {
-                        let line = Line::new(PlotPoints::from_iter(
-                            x_vals.iter().zip(thrust_vals.iter()).map(|(&x, &y)| [x, y]),
-                        ));
-                        plot_ui.line(line);
-                    });
-            });
-
-            columns[1].vertical(|ui| {
-                ui.group(|ui| {
-                    ui.set_min_size(egui::vec2(graph_width, graph_height));
-                    ui.vertical_centered(|ui| {
-                        ui.label(RichText::new("Temperatura Ambiente").size(16.0).strong());
-                    });
-
-                    Plot::new("temp_ambient_plot")
-                        .width(graph_width - 20.0)
-                        .height(graph_height - 40.0)
-                        .x_axis_label(if is_csv_mode {
-                            "Tiempo (s)"
-                        } else {
-                            "Muestras"
-                        })
-                        .y_axis_label("Temperatura (°C)")
-                        .show(ui, |plot_ui| {
-                            let line = Line::new(PlotPoints::from_iter(
-                                x_vals
-                                    .iter()
-                                    .zip(temp_ambient_vals.iter())
-                                    .map(|(&x, &y)| [x, y]),
-                            ));
-                            plot_ui.line(line);
-                        });
-                });
-
-                ui.add_space(10.0);
-
-                ui.group(|ui| {
-                    ui.set_min_size(egui::vec2(graph_width, graph_height));
-                    ui.vertical_centered(|ui| {
-                        ui.label(RichText::new("Temperatura Tobera").size(16.0).strong());
-                    });
+        // El primer gráfico del esquema ocupa la columna izquierda y el resto
+        // se apila a la derecha
+        let groups = self.schema.plot_groups();
+        let side_count = groups.len().saturating_sub(1).max(1) as f32;
+        let side_height = (graph_height * 2.0 + 20.0 - 10.0 * (side_count - 1.0)) / side_count;
+
+        if groups.len() == 1 {
+            self.show_plot_group(
+                ui,
+                &groups[0],
+                &data_points,
+                visible.clone(),
+                window,
+                egui::vec2(available_rect.width() - 20.0, graph_height * 2.0 + 20.0),
+                18.0,
+            );
+        } else {
+            ui.columns(2, |columns| {
+                self.show_plot_group(
+                    &mut columns[0],
+                    &groups[0],
+                    &data_points,
+                    visible.clone(),
+                    window,
+                    egui::vec2(graph_width, graph_height * 2.0 + 20.0),
+                    18.0,
+                );
 
-                    Plot::new("temp_nozzle_plot")
-                        .width(graph_width - 20.0)
-                        .height(graph_height - 40.0)
-                        .x_axis_label(if is_csv_mode {
-                            "Tiempo (s)"
-                        } else {
-                            "Muestras"
-                        })
-                        .y_axis_label("Temperatura (°C)")
-                        .show(ui, |plot_ui| {
-                            let line = Line::new(PlotPoints::from_iter(
-                                x_vals
-                                    .iter()
-                                    .zip(temp_nozzle_vals.iter())
-                                    .map(|(&x, &y)| [x, y]),
-                            ));
-                            plot_ui.line(line);
-                        });
+                columns[1].vertical(|ui| {
+                    for (i, group) in groups.iter().enumerate().skip(1) {
+                        if i > 1 {
+                            ui.add_space(10.0);
+                        }
+                        self.show_plot_group(
+                            ui,
+                            group,
+                            &data_points,
+                            visible.clone(),
+                            window,
+                            egui::vec2(graph_width, side_height),
+                            16.0,
+                        );
+                    }
                 });
             });
-        });
+        }
 
         ui.separator();
 
+        let motor_impulse = self.motor_impulse();
+        let burn_time = self.motor_burn_time(&data_points);
+        let designation = self.motor_designation(&data_points);
+        let mut thresholds_changed = false;
         ui.group(|ui| {
             ui.set_min_width(available_rect.width() - 20.0);
 
@@ -655,9 +1703,10 @@ impl App {
                     );
                 });
 
-                let max_thrust = thrust_vals.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-                let avg_thrust = thrust_vals.iter().sum::<f64>() / thrust_vals.len() as f64;
-                let duration = data_points.last().unwrap().time - data_points.first().unwrap().time;
+                let thrust_vals = dataset::channel(&data_points, self.schema.thrust_index());
+                let max_thrust = analysis::peak(&thrust_vals);
+                let avg_thrust = analysis::mean(&thrust_vals);
+                let duration = dataset::duration(&data_points);
 
                 ui.add_space(10.0);
                 ui.horizontal(|ui| {
@@ -665,16 +1714,105 @@ impl App {
                         ui.label(format!("📊 Empuje máximo: {:.2} N", max_thrust));
                         ui.label(format!("📈 Empuje promedio: {:.2} N", avg_thrust));
                         ui.label(format!("⏱️ Duración: {:.2} s", duration));
+                        ui.label(format!(
+                            "🚀 Impulso del archivo: {:.2} N⋅s",
+                            self.total_impulse
+                        ));
+                    });
+                    ui.separator();
+                    ui.vertical(|ui| match &self.burn {
+                        Some(burn) => {
+                            ui.label(format!(
+                                "🔥 Ignición: {:.3} s | Apagado: {:.3} s",
+                                burn.ignition_time, burn.burnout_time
+                            ));
+                            ui.label(format!("⏱️ Tiempo de combustión: {:.3} s", burn.burn_time));
+                            ui.label(format!("⏱️ Tiempo de acción: {:.3} s", burn.action_time));
+                            ui.label(format!(
+                                "📍 Tiempo al pico: {:.3} s ({:.2} N a {:.3} s)",
+                                burn.time_to_peak, burn.peak_thrust, burn.peak_time
+                            ));
+                            ui.label(format!(
+                                "📈 Empuje promedio en combustión: {:.2} N",
+                                burn.average_thrust
+                            ));
+                            ui.label(format!("🚀 Impulso de combustión: {:.2} N⋅s", burn.impulse));
+                        }
+                        None => {
+                            ui.colored_label(
+                                egui::Color32::YELLOW,
+                                "⚠️ No se detectó la combustión",
+                            );
+                        }
                     });
                     ui.separator();
                     ui.vertical(|ui| {
-                        ui.label(format!("🚀 Impulso total: {:.2} N⋅s", self.total_impulse));
-                        ui.label(format!(
-                            "⚡ Impulso específico: {:.2} s",
-                            self.total_impulse / 9.81
-                        ));
+                        match analysis::specific_impulse(motor_impulse, self.propellant_mass) {
+                            Some(isp) => {
+                                ui.label(format!("⚡ Impulso específico: {:.1} s", isp));
+                            }
+                            None => {
+                                ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    "⚠️ Ingrese la masa de propelente para calcular el Isp",
+                                );
+                            }
+                        }
                         ui.label(format!("📋 Muestras totales: {}", data_points.len()));
                     });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("🧪 Masa de propelente:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.propellant_mass)
+                                    .clamp_range(0.0..=1000.0)
+                                    .speed(0.001)
+                                    .suffix(" kg"),
+                            );
+                        });
+                        if let Some(flow) = analysis::mass_flow(self.propellant_mass, burn_time) {
+                            ui.label(format!("💨 Flujo másico: {:.3} kg/s", flow));
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("⏲️ Retardo:");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.motor_delay)
+                                    .desired_width(40.0),
+                            );
+                        });
+                        if let Some((designation, class)) = &designation {
+                            ui.label(
+                                RichText::new(format!(
+                                    "🏷️ {} ({:.0} % {})",
+                                    designation, class.percent, class.letter
+                                ))
+                                .strong(),
+                            );
+                        }
+                    });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        ui.label("Umbrales (% del pico):");
+                        let thresholds = &mut self.burn_thresholds;
+                        for (label, value) in [
+                            ("Ignición", &mut thresholds.ignition),
+                            ("Apagado", &mut thresholds.burnout),
+                            ("Acción", &mut thresholds.action),
+                        ] {
+                            ui.horizontal(|ui| {
+                                ui.label(label);
+                                thresholds_changed |= ui
+                                    .add(
+                                        egui::DragValue::new(value)
+                                            .clamp_range(0.5..=50.0)
+                                            .speed(0.1)
+                                            .suffix(" %"),
+                                    )
+                                    .changed();
+                            });
+                        }
+                    });
                 });
             } else {
                 ui.vertical_centered(|ui| {
@@ -684,8 +1822,8 @@ impl App {
                 ui.add_space(10.0);
                 ui.horizontal(|ui| {
                     ui.vertical(|ui| {
-                        ui.label(format!("🔗 Puerto: {}", self.port_name));
-                        ui.label(format!("⚡ Baud Rate: {}", self.baud_rate));
+                        ui.label(format!("🔗 Fuente: {}", self.source.describe()));
+                        ui.label(format!("🧾 Formato: {}", self.wire_format.label()));
                         ui.label(format!("📁 Archivo: {}", self.file_path));
                     });
                     ui.separator();
@@ -693,21 +1831,431 @@ impl App {
                         if !data_points.is_empty() {
                             ui.label(format!("📊 Muestras actuales: {}", data_points.len()));
                             let last_point = data_points.last().unwrap();
-                            ui.label(format!("🚀 Último empuje: {:.2} N", last_point.thrust));
-                            ui.label(format!(
-                                "🌡️ Temp. ambiente: {:.1}°C",
-                                last_point.temp_ambient
-                            ));
-                            ui.label(format!("🔥 Temp. tobera: {:.1}°C", last_point.temp_nozzle));
+                            for (channel, value) in
+                                self.schema.channels.iter().zip(last_point.values.iter())
+                            {
+                                let mut text =
+                                    format!("{}: {:.2} {}", channel.name, value, channel.unit);
+                                if let Some(kind) = channel.thermocouple {
+                                    text.push_str(&format!(" ({})", kind.label()));
+                                }
+                                ui.label(text);
+                            }
                         } else {
                             ui.label("⏳ Esperando datos...");
                             ui.label("🔌 Verificar conexión serial");
                             ui.label("📡 Iniciando captura de datos...");
                         }
                     });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        let status = self.source_status.lock().unwrap();
+                        let color = match status.state {
+                            LinkState::Connected => egui::Color32::GREEN,
+                            LinkState::Disconnected => egui::Color32::RED,
+                            _ => egui::Color32::YELLOW,
+                        };
+                        ui.colored_label(color, format!("📶 Enlace: {}", status.state.label()));
+                        if !status.peer.is_empty() {
+                            ui.label(format!("🛰️ Dispositivo: {}", status.peer));
+                        }
+                        ui.label(format!(
+                            "📨 Paquetes: {} ({} bytes)",
+                            status.packets, status.bytes
+                        ));
+                        ui.label(format!("🔁 Reconexiones: {}", status.reconnects));
+                        if let Some(last) = status.last_received {
+                            ui.label(format!(
+                                "⏱️ Último paquete: hace {:.1} s",
+                                last.elapsed().as_secs_f64()
+                            ));
+                        }
+                        if status.state != LinkState::Connected && !status.last_error.is_empty() {
+                            ui.colored_label(egui::Color32::RED, &status.last_error);
+                        }
+                        drop(status);
+
+                        // Comparación entre el reloj de la placa y el del host
+                        if let Some(clock) = *self.clock_stats.lock().unwrap() {
+                            ui.label(format!("🕰️ Deriva del reloj: {:+.1} ppm", clock.drift_ppm));
+                            ui.label(format!(
+                                "🐢 Latencia: {:.2} ms (jitter {:.2} ms)",
+                                clock.latency_ms, clock.jitter_ms
+                            ));
+                            if clock.resets > 0 {
+                                ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    format!("⚠️ Reloj reanclado {} veces", clock.resets),
+                                );
+                            }
+                        }
+                    });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        let stats = self.frame_stats.lock().unwrap();
+                        ui.label(format!("📦 Tramas válidas: {}", stats.frames));
+                        ui.label(format!("⚠️ Malformadas: {}", stats.malformed));
+                        ui.label(format!("✂️ Truncadas: {}", stats.truncated));
+                        ui.label(format!("📏 Sobredimensionadas: {}", stats.oversize));
+                        if self.wire_format == WireFormat::Binary {
+                            ui.label(format!("🧮 Errores de CRC: {}", stats.crc_errors));
+                            ui.label(format!("🕳️ Paquetes perdidos: {}", stats.lost));
+                        }
+                    });
                 });
             }
         });
+
+        // Con umbrales nuevos se vuelve a detectar la combustión
+        if thresholds_changed {
+            self.burn = self.detect_burn(&data_points);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn show_plot_group(
+        &self,
+        ui: &mut egui::Ui,
+        group: &PlotGroup,
+        data_points: &History,
+        visible: Range<usize>,
+        window: Option<[f64; 2]>,
+        size: egui::Vec2,
+        title_size: f32,
+    ) {
+        ui.group(|ui| {
+            ui.set_min_size(size);
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new(&group.title).size(title_size).strong());
+            });
+
+            // Todos los gráficos comparten el eje de tiempo y el cursor
+            let mut plot = Plot::new(format!("plot_{}", group.title))
+                .width(size.x - 20.0)
+                .height(size.y - 40.0)
+                .x_axis_label("Tiempo (s)")
+                .y_axis_label(format!("{} ({})", group.title, group.unit))
+                .link_axis("eje_tiempo", true, false)
+                .link_cursor("eje_tiempo", true, false);
+            if let Some([start, end]) = window {
+                plot = plot.include_x(start).include_x(end);
+            }
+
+            // Los límites de la combustión se marcan en el gráfico de empuje
+            let burn = self
+                .burn
+                .filter(|_| self.current_mode == AppMode::CsvViewer)
+                .filter(|_| group.channels.contains(&self.schema.thrust_index()));
+            let thrust_index = self.schema.thrust_index();
+            let reference = if group.channels.contains(&thrust_index) {
+                let indices = data_points.decimate(thrust_index, visible.clone(), PLOT_POINTS);
+                self.reference_overlay(data_points, &indices)
+            } else {
+                None
+            };
+            if group.channels.len() > 1 || burn.is_some() || reference.is_some() {
+                plot = plot.legend(Legend::default());
+            }
+
+            plot.show(ui, |plot_ui| {
+                // La ventana que avanza no se puede desplazar a mano; para
+                // revisar la sesión se elige "Todo"
+                if window.is_some() {
+                    plot_ui.set_auto_bounds(true.into());
+                }
+
+                for &channel in &group.channels {
+                    // Los valores ausentes (NaN) no se dibujan
+                    let points: Vec<[f64; 2]> = data_points
+                        .decimate(channel, visible.clone(), PLOT_POINTS)
+                        .into_iter()
+                        .map(|i| [data_points[i].time, data_points[i].values[channel]])
+                        .filter(|p| p[1].is_finite())
+                        .collect();
+                    let line = Line::new(PlotPoints::from(points))
+                        .name(&self.schema.channels[channel].name);
+                    plot_ui.line(line);
+                }
+
+                if let Some(burn) = burn {
+                    plot_ui.vline(VLine::new(burn.ignition_time).name("Ignición"));
+                    plot_ui.vline(VLine::new(burn.burnout_time).name("Apagado"));
+                }
+
+                if let Some(points) = reference {
+                    plot_ui.line(Line::new(PlotPoints::from(points)).name("Referencia"));
+                }
+            });
+        });
+    }
+
+    fn show_calibration_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.wizard.open;
+
+        egui::Window::new("⚖️ Calibración de la celda de carga")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let wizard = &mut self.wizard;
+
+                ui.horizontal(|ui| {
+                    ui.label("Nombre del perfil:");
+                    ui.text_edit_singleline(&mut wizard.name);
+                });
+
+                ui.add_space(10.0);
+                ui.label(RichText::new("Punto de referencia").strong());
+
+                ui.horizontal(|ui| {
+                    ui.label("Masa (kg):");
+                    ui.add(egui::DragValue::new(&mut wizard.mass).speed(0.01));
+                    ui.label("Lectura cruda:");
+                    ui.add(egui::DragValue::new(&mut wizard.raw).speed(1.0));
+                });
+
+                ui.horizontal(|ui| {
+                    // Promedio de las últimas lecturas sin convertir
+                    if ui.button("📥 Capturar lectura").clicked() {
+                        let raw_samples = self.raw_thrust.lock().unwrap();
+                        if raw_samples.is_empty() {
+                            wizard.message =
+                                "No hay lecturas: inicie el monitoreo en vivo".to_string();
+                        } else {
+                            wizard.raw = raw_samples.iter().sum::<f64>() / raw_samples.len() as f64;
+                            wizard.message =
+                                format!("Promedio de {} lecturas", raw_samples.len());
+                        }
+                    }
+
+                    if ui.button("➕ Agregar punto").clicked() {
+                        wizard.points.push(CalibrationPoint {
+                            mass: wizard.mass,
+                            raw: wizard.raw,
+                        });
+                        wizard.result = None;
+                    }
+                });
+
+                ui.add_space(10.0);
+
+                // Puntos capturados, con el residuo del último ajuste
+                let residuals = wizard.result.as_ref().map(|p| p.residuals());
+                let mut remove = None;
+                egui::Grid::new("puntos_calibracion")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(RichText::new("Masa (kg)").strong());
+                        ui.label(RichText::new("Crudo").strong());
+                        ui.label(RichText::new("Residuo (N)").strong());
+                        ui.label("");
+                        ui.end_row();
+
+                        for (i, point) in wizard.points.iter().enumerate() {
+                            ui.label(format!("{:.3}", point.mass));
+                            ui.label(format!("{:.1}", point.raw));
+                            match &residuals {
+                                Some(residuals) => ui.label(format!("{:+.3}", residuals[i])),
+                                None => ui.label("-"),
+                            };
+                            if ui.small_button("🗑").clicked() {
+                                remove = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(i) = remove {
+                    wizard.points.remove(i);
+                    wizard.result = None;
+                }
+
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Grado del polinomio:");
+                    egui::ComboBox::from_id_source("grado_calibracion")
+                        .selected_text(wizard.degree.to_string())
+                        .show_ui(ui, |ui| {
+                            for degree in 1..=MAX_DEGREE {
+                                ui.selectable_value(&mut wizard.degree, degree, degree.to_string());
+                            }
+                        });
+
+                    if ui.button("📐 Ajustar").clicked() {
+                        match CalibrationProfile::fit(&wizard.name, &wizard.points, wizard.degree) {
+                            Ok(profile) => {
+                                wizard.message =
+                                    format!("Residuo RMS: {:.3} N", profile.rms_residual());
+                                wizard.result = Some(profile);
+                            }
+                            Err(e) => {
+                                wizard.message = e;
+                                wizard.result = None;
+                            }
+                        }
+                    }
+                });
+
+                if let Some(profile) = &wizard.result {
+                    ui.label(format!("Coeficientes: {:?}", profile.coefficients));
+                }
+
+                if !wizard.message.is_empty() {
+                    ui.label(&wizard.message);
+                }
+
+                ui.add_space(10.0);
+
+                let can_save = wizard.result.is_some() && !wizard.name.trim().is_empty();
+                if ui
+                    .add_enabled(can_save, egui::Button::new("💾 Guardar perfil"))
+                    .clicked()
+                {
+                    if let Some(mut profile) = wizard.result.clone() {
+                        profile.name = wizard.name.trim().to_string();
+                        let name = profile.name.clone();
+                        self.calibrations.insert(profile);
+                        match self.calibrations.save(&self.calibration_path) {
+                            Ok(()) => {
+                                self.active_calibration = name.clone();
+                                wizard.message = format!(
+                                    "Perfil \"{}\" guardado en {}; se aplica desde el próximo inicio",
+                                    name, self.calibration_path
+                                );
+                            }
+                            Err(e) => wizard.message = e,
+                        }
+                    }
+                }
+            });
+
+        self.wizard.open = open;
+    }
+
+    fn show_motor_export_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.motor_export.open;
+        let mut export = None;
+
+        egui::Window::new("🚀 Exportar curva del motor")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let form = &mut self.motor_export;
+                egui::Grid::new("formulario_eng").show(ui, |ui| {
+                    ui.label("Archivo:");
+                    ui.text_edit_singleline(&mut form.path);
+                    ui.end_row();
+
+                    ui.label("Nombre:");
+                    ui.text_edit_singleline(&mut form.header.name);
+                    ui.end_row();
+
+                    ui.label("Diámetro:");
+                    ui.add(
+                        egui::DragValue::new(&mut form.header.diameter)
+                            .clamp_range(0.0..=1000.0)
+                            .suffix(" mm"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Largo:");
+                    ui.add(
+                        egui::DragValue::new(&mut form.header.length)
+                            .clamp_range(0.0..=5000.0)
+                            .suffix(" mm"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Retardos:");
+                    ui.text_edit_singleline(&mut form.header.delays);
+                    ui.end_row();
+
+                    ui.label("Masa de propelente:");
+                    ui.add(
+                        egui::DragValue::new(&mut form.header.propellant_mass)
+                            .clamp_range(0.0..=1000.0)
+                            .speed(0.001)
+                            .suffix(" kg"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Masa total:");
+                    ui.add(
+                        egui::DragValue::new(&mut form.header.total_mass)
+                            .clamp_range(0.0..=1000.0)
+                            .speed(0.001)
+                            .suffix(" kg"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Fabricante:");
+                    ui.text_edit_singleline(&mut form.header.manufacturer);
+                    ui.end_row();
+
+                    ui.label("Máximo de puntos:");
+                    ui.add(egui::DragValue::new(&mut form.max_points).clamp_range(2..=10000));
+                    ui.end_row();
+                });
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("💾 RASP (.eng)").clicked() {
+                        export = Some("eng");
+                    }
+                    if ui.button("💾 RockSim (.rse)").clicked() {
+                        export = Some("rse");
+                    }
+                });
+                if !form.message.is_empty() {
+                    ui.label(&form.message);
+                }
+            });
+
+        if let Some(extension) = export {
+            let path = Path::new(&self.motor_export.path)
+                .with_extension(extension)
+                .to_string_lossy()
+                .to_string();
+            self.motor_export.message = match self.export_motor(&path) {
+                Ok(count) => format!("{} puntos guardados en {}", count, path),
+                Err(e) => e,
+            };
+        }
+        self.motor_export.open = open;
+    }
+
+    // Escribe la curva de la combustión detectada; el formato sale de la
+    // extensión del archivo
+    fn export_motor(&self, path: &str) -> Result<usize, String> {
+        let burn = self
+            .burn
+            .ok_or("No se detectó la combustión; ajuste los umbrales")?;
+        let form = &self.motor_export;
+        if form.header.total_mass < form.header.propellant_mass {
+            return Err("La masa total no puede ser menor que la de propelente".to_string());
+        }
+
+        let data_points = self.data_points.lock().unwrap();
+        let thrust_index = self.schema.thrust_index();
+        let times: Vec<f64> = data_points.iter().map(|dp| dp.time).collect();
+        let thrust: Vec<f64> = data_points
+            .iter()
+            .map(|dp| dp.values[thrust_index])
+            .collect();
+
+        let curve = engine::burn_curve(&times, &thrust, burn.ignition_time, burn.burnout_time);
+        let points = engine::reduce_points(&curve, form.max_points);
+        if path.ends_with(".rse") {
+            // Las masas de un .rse importado se conservan, llevadas a los
+            // tiempos de la curva exportada (que parten de la ignición)
+            let masses = (!form.masses.is_empty()).then(|| {
+                let times: Vec<f64> = points.iter().map(|p| p[0] + burn.ignition_time).collect();
+                engine::masses_at(&form.masses, &times)
+            });
+            engine::write_rse(path, &form.header, &points, masses.as_deref())?;
+        } else {
+            engine::write_eng(path, &form.header, &points)?;
+        }
+        Ok(points.len())
     }
 
     fn export_summary(&self) {
@@ -716,32 +2264,15 @@ impl App {
             return;
         }
 
-        let summary_file = "resumen_analisis.txt";
-        if let Ok(mut file) = std::fs::File::create(summary_file) {
-            let y1_vals: Vec<f64> = data_points.iter().map(|dp| dp.thrust).collect();
-            let max_thrust = y1_vals.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-            let avg_thrust = y1_vals.iter().sum::<f64>() / y1_vals.len() as f64;
-            let duration = data_points.last().unwrap().time - data_points.first().unwrap().time;
-
-            let _ = writeln!(file, "=== RESUMEN DEL ANÁLISIS ===");
-            let _ = writeln!(file, "Archivo analizado: {}", self.csv_file_path);
-            let _ = writeln!(file, "");
-            let _ = writeln!(file, "ESTADÍSTICAS DE EMPUJE:");
-            let _ = writeln!(file, "Empuje máximo: {:.2} N", max_thrust);
-            let _ = writeln!(file, "Empuje promedio: {:.2} N", avg_thrust);
-            let _ = writeln!(file, "");
-            let _ = writeln!(file, "IMPULSO:");
-            let _ = writeln!(file, "Impulso total: {:.2} N⋅s", self.total_impulse);
-            let _ = writeln!(
-                file,
-                "Impulso específico: {:.2} s",
-                self.total_impulse / 9.81
-            );
-            let _ = writeln!(file, "");
-            let _ = writeln!(file, "DURACIÓN:");
-            let _ = writeln!(file, "Duración total: {:.2} s", duration);
-            let _ = writeln!(file, "Muestras totales: {}", data_points.len());
-        }
+        let report = Report::new(
+            &self.csv_file_path,
+            &self.schema,
+            &data_points,
+            self.burn_thresholds,
+            self.propellant_mass,
+            &self.motor_delay,
+        );
+        let _ = std::fs::write("resumen_analisis.txt", report.to_text());
     }
 }
 impl eframe::App for App {
@@ -769,12 +2300,34 @@ impl eframe::App for App {
             AppMode::LiveMonitoring | AppMode::CsvViewer => {
                 self.show_monitoring_ui(ui);
             }
+            AppMode::Comparison => {
+                self.show_comparison_ui(ui);
+            }
         });
 
+        if self.wizard.open {
+            self.show_calibration_window(ctx);
+        }
+
+        if self.motor_export.open {
+            self.show_motor_export_window(ctx);
+        }
+
         ctx.request_repaint();
     }
+
+    // Cerrar la ventana a mitad de un ensayo también cierra la sesión, igual
+    // que Detener, para no perder los archivos que se escriben al final
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.stop_acquisition();
+    }
 }
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([500.0, 600.0]),
         ..Default::default()
@@ -0,0 +1,153 @@
+// Resumen del análisis de un ensayo.
+//
+// Reúne las mismas métricas del panel de estadísticas; lo usan el archivo
+// `resumen_analisis.txt` y el comando `apogeo analyze`.
+
+use crate::analysis::{self, BurnMetrics, BurnThresholds};
+use crate::dataset::{self, DataPoint};
+use crate::schema::Schema;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Report {
+    #[serde(rename = "archivo")]
+    pub file: String,
+    #[serde(rename = "muestras")]
+    pub samples: usize,
+    #[serde(rename = "duracion_s")]
+    pub duration: f64,
+    #[serde(rename = "empuje_maximo_n")]
+    pub max_thrust: f64,
+    #[serde(rename = "empuje_promedio_n")]
+    pub average_thrust: f64,
+    #[serde(rename = "impulso_archivo_ns")]
+    pub total_impulse: f64,
+    #[serde(rename = "umbrales")]
+    pub thresholds: BurnThresholds,
+    #[serde(rename = "combustion")]
+    pub burn: Option<BurnMetrics>,
+    #[serde(rename = "masa_propelente_kg")]
+    pub propellant_mass: Option<f64>,
+    #[serde(rename = "impulso_especifico_s")]
+    pub isp: Option<f64>,
+    #[serde(rename = "flujo_masico_kg_s")]
+    pub mass_flow: Option<f64>,
+    #[serde(rename = "designacion")]
+    pub designation: Option<String>,
+    #[serde(rename = "clase")]
+    pub class: Option<String>,
+    #[serde(rename = "porcentaje_clase")]
+    pub class_percent: Option<f64>,
+}
+
+impl Report {
+    // `propellant_mass` en kg, 0 si no se conoce; `data_points` no puede
+    // estar vacío
+    pub fn new(
+        file: &str,
+        schema: &Schema,
+        data_points: &[DataPoint],
+        thresholds: BurnThresholds,
+        propellant_mass: f64,
+        delay: &str,
+    ) -> Self {
+        let times = dataset::times(data_points);
+        let thrust = dataset::channel(data_points, schema.thrust_index());
+        let duration = dataset::duration(data_points);
+        let total_impulse = analysis::impulse(&times, &thrust);
+        let burn = analysis::detect_burn(&times, &thrust, &thresholds);
+
+        let impulse = analysis::motor_impulse(burn, total_impulse);
+        let burn_time = analysis::motor_burn_time(burn, duration);
+        let designation = analysis::motor_designation(burn, total_impulse, duration, delay);
+
+        Self {
+            file: file.to_string(),
+            samples: data_points.len(),
+            duration,
+            max_thrust: analysis::peak(&thrust),
+            average_thrust: analysis::mean(&thrust),
+            total_impulse,
+            thresholds,
+            burn,
+            propellant_mass: (propellant_mass > 0.0).then_some(propellant_mass),
+            isp: analysis::specific_impulse(impulse, propellant_mass),
+            mass_flow: analysis::mass_flow(propellant_mass, burn_time),
+            class: designation.as_ref().map(|(_, class)| class.letter.clone()),
+            class_percent: designation.as_ref().map(|(_, class)| class.percent),
+            designation: designation.map(|(designation, _)| designation),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let mut line = |l: String| {
+            text.push_str(&l);
+            text.push('\n');
+        };
+
+        line("=== RESUMEN DEL ANÁLISIS ===".to_string());
+        line(format!("Archivo analizado: {}", self.file));
+        line(String::new());
+        line("ESTADÍSTICAS DE EMPUJE:".to_string());
+        line(format!("Empuje máximo: {:.2} N", self.max_thrust));
+        line(format!("Empuje promedio: {:.2} N", self.average_thrust));
+        line(String::new());
+        if let Some(burn) = &self.burn {
+            line("COMBUSTIÓN:".to_string());
+            line(format!(
+                "Umbrales: ignición {:.1} %, apagado {:.1} %, acción {:.1} %",
+                self.thresholds.ignition, self.thresholds.burnout, self.thresholds.action
+            ));
+            line(format!("Ignición: {:.3} s", burn.ignition_time));
+            line(format!("Apagado: {:.3} s", burn.burnout_time));
+            line(format!("Tiempo de combustión: {:.3} s", burn.burn_time));
+            line(format!("Tiempo de acción: {:.3} s", burn.action_time));
+            line(format!(
+                "Tiempo al pico: {:.3} s ({:.2} N a {:.3} s)",
+                burn.time_to_peak, burn.peak_thrust, burn.peak_time
+            ));
+            line(format!(
+                "Empuje promedio en combustión: {:.2} N",
+                burn.average_thrust
+            ));
+            line(String::new());
+        }
+        line("IMPULSO:".to_string());
+        line(format!(
+            "Impulso del archivo: {:.2} N⋅s",
+            self.total_impulse
+        ));
+        if let Some(burn) = &self.burn {
+            line(format!("Impulso de combustión: {:.2} N⋅s", burn.impulse));
+        }
+        match (self.isp, self.propellant_mass) {
+            (Some(isp), Some(mass)) => {
+                line(format!("Masa de propelente: {:.3} kg", mass));
+                line(format!("Impulso específico: {:.1} s", isp));
+            }
+            _ => {
+                line("Impulso específico: no calculado (falta la masa de propelente)".to_string());
+            }
+        }
+        if let Some(flow) = self.mass_flow {
+            line(format!("Flujo másico: {:.3} kg/s", flow));
+        }
+        line(String::new());
+        if let (Some(designation), Some(class), Some(percent)) =
+            (&self.designation, &self.class, self.class_percent)
+        {
+            line("CLASIFICACIÓN:".to_string());
+            line(format!("Designación: {}", designation));
+            line(format!(
+                "Clase de impulso: {} ({:.0} % de la clase)",
+                class, percent
+            ));
+            line(String::new());
+        }
+        line("DURACIÓN:".to_string());
+        line(format!("Duración total: {:.2} s", self.duration));
+        line(format!("Muestras totales: {}", self.samples));
+        text
+    }
+}
@@ -0,0 +1,315 @@
+// Esquema de canales de telemetría.
+//
+// Define qué canales envía el banco, en qué orden llegan y en qué gráfico
+// se dibujan. Se lee desde un archivo TOML como este:
+//
+//   [[canal]]
+//   id = "thrust"
+//   nombre = "Empuje"
+//   unidad = "N"
+//   grafico = "Empuje"
+//
+// El orden de los bloques `[[canal]]` es el orden de los campos en el
+// cable y de las columnas en el CSV (después de la columna de tiempo).
+//
+// Un canal con `termopar = "K"` (o "J", "T") recibe milivolts sin
+// linealizar; si además tiene `mv_por_cuenta`, recibe cuentas de ADC.
+//
+// Si la placa envía su reloj (micros() o millis()) como primer campo de
+// cada registro, se declara con:
+//
+//   [marca_tiempo]
+//   unidad = "us"   # o "ms"
+//   bits = 32       # ancho del contador, da la vuelta en 2^bits; la
+//                   # vuelta debe durar al menos 1 s
+
+use crate::thermocouple::ThermocoupleType;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// Identificadores que el análisis necesita reconocer
+pub const THRUST: &str = "thrust";
+pub const TEMP_AMBIENT: &str = "temp_ambient";
+pub const TEMP_NOZZLE: &str = "temp_nozzle";
+
+// Primera columna del registro: tiempo desde Iniciar, hh:mm:ss:mmm
+pub const TIME_COLUMN: &str = "Tiempo";
+
+// Columnas del CSV con ambas bases de tiempo, en segundos, cuando la placa
+// envía su reloj
+pub const DEVICE_TIME_COLUMN: &str = "Tiempo placa (s)";
+pub const HOST_TIME_COLUMN: &str = "Tiempo host (s)";
+
+// Columna de tiempo de los CSV procesados, en segundos
+pub const SECONDS_COLUMN: &str = "Tiempo (s)";
+
+// Primera columna de un encabezado, según el tipo de CSV
+pub const TIME_COLUMNS: [&str; 4] = [
+    TIME_COLUMN,
+    SECONDS_COLUMN,
+    DEVICE_TIME_COLUMN,
+    HOST_TIME_COLUMN,
+];
+
+// Última columna del CSV: hora absoluta de cada muestra
+pub const UTC_COLUMN: &str = "UTC";
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ClockUnit {
+    #[serde(rename = "us")]
+    Micros,
+    #[serde(rename = "ms")]
+    Millis,
+}
+
+// Vuelta mínima del contador de la placa, en segundos
+pub const MIN_CLOCK_LAP: f64 = 1.0;
+
+// Contador de tiempo de la placa
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct DeviceClock {
+    #[serde(rename = "unidad")]
+    pub unit: ClockUnit,
+    #[serde(default = "default_clock_bits")]
+    pub bits: u32,
+}
+
+fn default_clock_bits() -> u32 {
+    32
+}
+
+impl DeviceClock {
+    pub fn seconds_per_tick(&self) -> f64 {
+        match self.unit {
+            ClockUnit::Micros => 1e-6,
+            ClockUnit::Millis => 1e-3,
+        }
+    }
+
+    // Valores posibles del contador antes de dar la vuelta
+    pub fn modulus(&self) -> u64 {
+        1 << self.bits
+    }
+
+    // Segundos que tarda el contador en dar la vuelta
+    pub fn lap_seconds(&self) -> f64 {
+        self.modulus() as f64 * self.seconds_per_tick()
+    }
+
+    // Contador que enviaría la placa a los `time` segundos de encendida
+    pub fn counter(&self, time: f64) -> u32 {
+        ((time.max(0.0) / self.seconds_per_tick()).round() as u64 % self.modulus()) as u32
+    }
+
+    pub fn describe(&self) -> String {
+        let unit = match self.unit {
+            ClockUnit::Micros => "µs",
+            ClockUnit::Millis => "ms",
+        };
+        format!("{} de {} bits", unit, self.bits)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Channel {
+    pub id: String,
+    #[serde(rename = "nombre")]
+    pub name: String, // nombre visible y encabezado de la columna
+    #[serde(rename = "unidad")]
+    pub unit: String,
+    #[serde(rename = "grafico")]
+    pub plot: String, // título del gráfico donde se dibuja
+    #[serde(rename = "termopar", default, skip_serializing_if = "Option::is_none")]
+    pub thermocouple: Option<ThermocoupleType>,
+    #[serde(
+        rename = "mv_por_cuenta",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mv_per_count: Option<f64>, // solo si el termopar llega en cuentas de ADC
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Schema {
+    #[serde(
+        rename = "marca_tiempo",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub device_clock: Option<DeviceClock>,
+    #[serde(rename = "canal")]
+    pub channels: Vec<Channel>,
+}
+
+// Gráfico armado a partir de los canales que comparten título
+pub struct PlotGroup {
+    pub title: String,
+    pub unit: String,
+    pub channels: Vec<usize>, // índices en Schema::channels
+}
+
+impl Default for Schema {
+    // Los tres canales del banco original
+    fn default() -> Self {
+        let channel = |id: &str, name: &str, unit: &str| Channel {
+            id: id.to_string(),
+            name: name.to_string(),
+            unit: unit.to_string(),
+            plot: name.to_string(),
+            thermocouple: None,
+            mv_per_count: None,
+        };
+        Self {
+            device_clock: None,
+            channels: vec![
+                channel(THRUST, "Empuje", "N"),
+                channel(TEMP_AMBIENT, "Temperatura Ambiente", "°C"),
+                channel(TEMP_NOZZLE, "Temperatura Tobera", "°C"),
+            ],
+        }
+    }
+}
+
+impl Schema {
+    // Carga el esquema; si el archivo no existe se usa el esquema por defecto
+    pub fn load(path: &str) -> Result<Self, String> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Error al leer el esquema: {}", e))?;
+        let schema: Schema =
+            toml::from_str(&text).map_err(|e| format!("Esquema inválido: {}", e))?;
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.channels.is_empty() {
+            return Err("El esquema no define canales".to_string());
+        }
+        for (i, channel) in self.channels.iter().enumerate() {
+            if channel.id.is_empty() {
+                return Err(format!("El canal {} no tiene id", i + 1));
+            }
+            if self.channels[..i].iter().any(|c| c.id == channel.id) {
+                return Err(format!("Id de canal repetido: {}", channel.id));
+            }
+        }
+        if let Some(clock) = self.device_clock {
+            // En el formato binario el contador viaja como u32
+            if !(8..=32).contains(&clock.bits) {
+                return Err("El reloj de la placa debe tener entre 8 y 32 bits".to_string());
+            }
+            // Con vueltas más cortas no se sabe cuántas dio entre dos
+            // registros si el host los recibe con algo de demora
+            if clock.lap_seconds() < MIN_CLOCK_LAP {
+                return Err(format!(
+                    "El reloj de la placa da la vuelta cada {:.3} s; debe durar al menos {} s \
+                     (10 bits en ms o 20 en us)",
+                    clock.lap_seconds(),
+                    MIN_CLOCK_LAP
+                ));
+            }
+        }
+        if self.index(THRUST).is_none() {
+            return Err(format!(
+                "El esquema debe incluir un canal con id \"{}\"",
+                THRUST
+            ));
+        }
+
+        // La temperatura ambiente es la referencia de junta fría
+        if self.channels.iter().any(|c| c.thermocouple.is_some()) {
+            match self.index(TEMP_AMBIENT) {
+                None => {
+                    return Err(format!(
+                        "Los termopares necesitan un canal \"{}\" como junta fría",
+                        TEMP_AMBIENT
+                    ))
+                }
+                Some(i) if self.channels[i].thermocouple.is_some() => {
+                    return Err(format!(
+                        "El canal \"{}\" debe llegar en °C, no como termopar",
+                        TEMP_AMBIENT
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn index(&self, id: &str) -> Option<usize> {
+        self.channels.iter().position(|c| c.id == id)
+    }
+
+    pub fn thrust_index(&self) -> usize {
+        // validate() garantiza que existe
+        self.index(THRUST).unwrap_or(0)
+    }
+
+    // Convierte a °C los canales de termopar de un registro recibido
+    pub fn convert_thermocouples(&self, values: &mut [f64]) {
+        let cold_junction = match self.index(TEMP_AMBIENT) {
+            Some(i) => values[i],
+            None => return,
+        };
+        for (channel, value) in self.channels.iter().zip(values.iter_mut()) {
+            if let Some(kind) = channel.thermocouple {
+                let millivolts = *value * channel.mv_per_count.unwrap_or(1.0);
+                *value = kind.celsius(millivolts, cold_junction);
+            }
+        }
+    }
+
+    // Inversa de `convert_thermocouples`: lleva los °C de un registro a lo
+    // que enviaría la placa (mV o cuentas de ADC)
+    pub fn encode_thermocouples(&self, values: &mut [f64]) {
+        let cold_junction = match self.index(TEMP_AMBIENT) {
+            Some(i) => values[i],
+            None => return,
+        };
+        for (channel, value) in self.channels.iter().zip(values.iter_mut()) {
+            if let Some(kind) = channel.thermocouple {
+                let millivolts = kind
+                    .millivolts(*value)
+                    .zip(kind.millivolts(cold_junction))
+                    .map_or(f64::NAN, |(hot, cold)| hot - cold);
+                *value = millivolts / channel.mv_per_count.unwrap_or(1.0);
+            }
+        }
+    }
+
+    // Encabezado del CSV de registro
+    pub fn csv_header(&self) -> String {
+        let mut header = String::from(TIME_COLUMN);
+        for channel in &self.channels {
+            header.push(',');
+            header.push_str(&channel.name);
+        }
+        if self.device_clock.is_some() {
+            header.push_str(&format!(",{},{}", DEVICE_TIME_COLUMN, HOST_TIME_COLUMN));
+        }
+        header.push(',');
+        header.push_str(UTC_COLUMN);
+        header
+    }
+
+    // Agrupa los canales por gráfico, en el orden en que aparecen
+    pub fn plot_groups(&self) -> Vec<PlotGroup> {
+        let mut groups: Vec<PlotGroup> = Vec::new();
+        for (i, channel) in self.channels.iter().enumerate() {
+            match groups.iter_mut().find(|g| g.title == channel.plot) {
+                Some(group) => group.channels.push(i),
+                None => groups.push(PlotGroup {
+                    title: channel.plot.clone(),
+                    unit: channel.unit.clone(),
+                    channels: vec![i],
+                }),
+            }
+        }
+        groups
+    }
+}
@@ -0,0 +1,201 @@
+// Sesiones de ensayo.
+//
+// Cada "Iniciar" crea una carpeta con fecha dentro de la carpeta de ensayos,
+// para que los ensayos seguidos no terminen concatenados en un solo CSV:
+//
+//   ensayos/2026-03-14_150926_b-12/
+//     registro.csv    registro tal como se adquirió, con comentarios y eventos
+//     registro.cap    captura cruda de todo lo recibido, para reprocesar
+//     procesado.csv   solo los datos, con el tiempo en segundos
+//     metadatos.json  motor, lote de propelente, masa, operador, clima, notas
+//     eventos.csv     tara, enlace, marcas del operador, etc.
+//     resumen.txt     el mismo resumen que "Exportar resumen"
+//
+// Los tres últimos se escriben al terminar la sesión.
+
+use crate::analysis::BurnThresholds;
+use crate::dataset;
+use crate::report::Report;
+use crate::schema::Schema;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+pub const LOG_FILE: &str = "registro.csv";
+pub const PROCESSED_FILE: &str = "procesado.csv";
+pub const METADATA_FILE: &str = "metadatos.json";
+pub const EVENTS_FILE: &str = "eventos.csv";
+pub const SUMMARY_FILE: &str = "resumen.txt";
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionInfo {
+    pub motor: String,
+    #[serde(rename = "lote_propelente")]
+    pub propellant_batch: String,
+    #[serde(rename = "masa_propelente_kg")]
+    pub propellant_mass: f64,
+    #[serde(rename = "operador")]
+    pub operator: String,
+    #[serde(rename = "clima")]
+    pub weather: String,
+    #[serde(rename = "notas")]
+    pub notes: String,
+    #[serde(rename = "inicio")]
+    pub started_at: String, // hora local, ISO-8601
+    #[serde(rename = "fin", skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+pub struct Session {
+    pub dir: PathBuf,
+    pub info: SessionInfo,
+}
+
+impl Session {
+    // Crea la carpeta de la sesión y escribe sus metadatos
+    pub fn create(root: &str, info: &SessionInfo) -> Result<Self, String> {
+        let now = Local::now();
+        let mut name = now.format("%Y-%m-%d_%H%M%S").to_string();
+        let motor = slug(&info.motor);
+        if !motor.is_empty() {
+            name.push('_');
+            name.push_str(&motor);
+        }
+
+        let dir = Path::new(root).join(name);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Error al crear la carpeta de la sesión: {}", e))?;
+
+        let session = Self {
+            dir,
+            info: SessionInfo {
+                started_at: now.to_rfc3339(),
+                finished_at: None,
+                ..info.clone()
+            },
+        };
+        session.write_metadata()?;
+        Ok(session)
+    }
+
+    // Abre una sesión existente a partir de su carpeta
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(dir.join(METADATA_FILE))
+            .map_err(|e| format!("Error al leer los metadatos: {}", e))?;
+        let info =
+            serde_json::from_str(&text).map_err(|e| format!("Metadatos inválidos: {}", e))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            info,
+        })
+    }
+
+    // Sesiones de la carpeta de ensayos, de la más reciente a la más antigua
+    pub fn list(root: &str) -> Vec<Session> {
+        let entries = match std::fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let mut sessions: Vec<Session> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| Session::open(&entry.path()).ok())
+            .collect();
+        sessions.sort_by(|a, b| b.dir.cmp(&a.dir));
+        sessions
+    }
+
+    pub fn name(&self) -> String {
+        self.dir
+            .file_name()
+            .map_or(String::new(), |n| n.to_string_lossy().to_string())
+    }
+
+    pub fn path(&self, file: &str) -> String {
+        self.dir.join(file).to_string_lossy().to_string()
+    }
+
+    pub fn log_path(&self) -> String {
+        self.path(LOG_FILE)
+    }
+
+    fn write_metadata(&self) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(&self.info)
+            .map_err(|e| format!("Error al guardar los metadatos: {}", e))?;
+        std::fs::write(self.dir.join(METADATA_FILE), text)
+            .map_err(|e| format!("Error al guardar los metadatos: {}", e))
+    }
+
+    // Cierra la sesión: eventos, datos procesados, resumen y hora de fin. Si
+    // el registro quedó sin datos solo se anotan los eventos y el fin
+    pub fn finish(&mut self, schema: &Schema, thresholds: BurnThresholds) -> Result<(), String> {
+        let log_path = self.log_path();
+        self.write_events(&log_path)?;
+
+        if let Ok(dataset) = dataset::load(schema, &log_path) {
+            dataset::write_csv(schema, &dataset.data_points, &self.path(PROCESSED_FILE))?;
+            let mass = dataset.propellant_mass.unwrap_or(self.info.propellant_mass);
+            let report = Report::new(
+                &log_path,
+                schema,
+                &dataset.data_points,
+                thresholds,
+                mass,
+                "",
+            );
+            std::fs::write(self.path(SUMMARY_FILE), report.to_text())
+                .map_err(|e| format!("Error al guardar el resumen: {}", e))?;
+        }
+
+        self.info.finished_at = Some(Local::now().to_rfc3339());
+        self.write_metadata()
+    }
+
+    // Pasa los comentarios `# evento: tipo | tiempo ... | detalle` del
+    // registro a un CSV propio
+    fn write_events(&self, log_path: &str) -> Result<(), String> {
+        let file = std::fs::File::open(log_path)
+            .map_err(|e| format!("Error al abrir el archivo: {}", e))?;
+        let mut lines = vec!["tiempo,tipo,detalle".to_string()];
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            let event = match line.strip_prefix("# evento:") {
+                Some(event) => event,
+                None => continue,
+            };
+            let mut parts = event.split('|').map(|p| p.trim());
+            let kind = parts.next().unwrap_or_default();
+            let mut time = "";
+            let mut details = Vec::new();
+            for part in parts {
+                match part.strip_prefix("tiempo ") {
+                    Some(t) => time = t,
+                    None if !part.is_empty() => details.push(part),
+                    None => {}
+                }
+            }
+            lines.push(format!(
+                "{},{},\"{}\"",
+                time,
+                kind,
+                details.join("; ").replace('"', "\"\"")
+            ));
+        }
+        std::fs::write(self.path(EVENTS_FILE), lines.join("\n") + "\n")
+            .map_err(|e| format!("Error al guardar los eventos: {}", e))
+    }
+}
+
+// Nombre apto para carpeta: minúsculas, dígitos y guiones
+fn slug(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
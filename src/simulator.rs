@@ -0,0 +1,297 @@
+// Simulador del banco de pruebas sobre un pseudo-terminal.
+//
+// Reproduce un registro CSV (o una curva de empuje sintética) en el formato
+// del cable, a velocidad real o escalada. La interfaz se conecta a él como a
+// cualquier puerto serie, lo que permite ensayar procedimientos, entrenar
+// operadores y reproducir fallas sin el banco:
+//
+//   apogeo simulate --csv ensayo.csv --speed 2
+//   apogeo simulate --synthetic --wire binary
+//
+// El empuje se envía tal como está en el registro (N), así que se debe
+// monitorear sin perfil de calibración. Los termopares se envían en mV (o
+// en cuentas si el canal tiene `mv_por_cuenta`), igual que la placa.
+
+use crate::dataset::DataPoint;
+use crate::framing::WireFormat;
+use crate::schema::{self, Schema};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Enlace estable al pseudo-terminal, para no buscar /dev/pts/N cada vez
+pub const SIMULATOR_LINK: &str = "/tmp/apogeo-simulador";
+
+// Puerto del simulador si hay uno corriendo
+pub fn available_port() -> Option<String> {
+    std::path::Path::new(SIMULATOR_LINK)
+        .exists()
+        .then(|| SIMULATOR_LINK.to_string())
+}
+
+// Curva sintética: reposo, subida rápida, meseta regresiva y cola
+#[derive(Clone, Copy)]
+pub struct SyntheticCurve {
+    pub peak_thrust: f64, // N
+    pub burn_time: f64,   // s
+    pub rate: f64,        // muestras por segundo
+}
+
+impl Default for SyntheticCurve {
+    fn default() -> Self {
+        Self {
+            peak_thrust: 150.0,
+            burn_time: 2.0,
+            rate: 100.0,
+        }
+    }
+}
+
+// Segundos de reposo antes de la ignición y después del apagado
+const IDLE_BEFORE: f64 = 2.0;
+const IDLE_AFTER: f64 = 3.0;
+const AMBIENT: f64 = 20.0; // °C
+
+impl SyntheticCurve {
+    fn thrust(&self, t: f64) -> f64 {
+        let rise = 0.05 * self.burn_time;
+        let tail = 0.9 * self.burn_time;
+        if t <= 0.0 || t >= self.burn_time {
+            0.0
+        } else if t < rise {
+            let x = t / rise;
+            self.peak_thrust * x * x * (3.0 - 2.0 * x)
+        } else if t < tail {
+            // Regresiva hasta el 70 % del pico
+            self.peak_thrust * (1.0 - 0.3 * (t - rise) / (tail - rise))
+        } else {
+            let x = (t - tail) / (self.burn_time - tail);
+            0.7 * self.peak_thrust * (1.0 - x).powi(2)
+        }
+    }
+
+    fn nozzle_temperature(&self, t: f64) -> f64 {
+        if t <= 0.0 {
+            return AMBIENT;
+        }
+        let heating = |t: f64| 600.0 * (1.0 - (-t / 0.8).exp());
+        if t < self.burn_time {
+            AMBIENT + heating(t)
+        } else {
+            AMBIENT + heating(self.burn_time) * (-(t - self.burn_time) / 10.0).exp()
+        }
+    }
+
+    // Sesión completa en las unidades del registro (N y °C)
+    pub fn session(&self, schema: &Schema) -> Vec<DataPoint> {
+        let thrust = schema.thrust_index();
+        let ambient = schema.index(schema::TEMP_AMBIENT);
+        let nozzle = schema.index(schema::TEMP_NOZZLE);
+
+        // Ruido reproducible, sin depender de un generador externo
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut noise = move |amplitude: f64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            amplitude * ((seed % 2001) as f64 / 1000.0 - 1.0)
+        };
+
+        let total = IDLE_BEFORE + self.burn_time + IDLE_AFTER;
+        let samples = (total * self.rate).round() as usize;
+        (0..=samples)
+            .map(|i| {
+                let time = i as f64 / self.rate;
+                let t = time - IDLE_BEFORE;
+                let mut values = vec![0.0; schema.channels.len()];
+                values[thrust] = self.thrust(t) + noise(0.005 * self.peak_thrust);
+                if let Some(i) = ambient {
+                    values[i] = AMBIENT + noise(0.05);
+                }
+                if let Some(i) = nozzle {
+                    values[i] = self.nozzle_temperature(t) + noise(0.5);
+                }
+                DataPoint { time, values }
+            })
+            .collect()
+    }
+}
+
+// Registro tal como lo enviaría la placa
+pub fn encode_point(
+    schema: &Schema,
+    wire_format: WireFormat,
+    sequence: u16,
+    point: &DataPoint,
+) -> Result<Vec<u8>, String> {
+    let mut values = point.values.clone();
+    schema.encode_thermocouples(&mut values);
+    // Los canales que el archivo no trae se envían en cero
+    for value in values.iter_mut().filter(|v| !v.is_finite()) {
+        *value = 0.0;
+    }
+    let counter = schema.device_clock.map(|clock| clock.counter(point.time));
+    wire_format.encode(sequence, counter, &values)
+}
+
+pub struct SimulatorConfig {
+    pub points: Vec<DataPoint>,
+    pub wire_format: WireFormat,
+    pub speed: f64,   // 1 = tiempo real
+    pub repeat: bool, // vuelve a empezar al terminar
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct SimulatorStats {
+    pub sent: u64,
+    pub dropped: u64, // registros descartados porque nadie leía el puerto
+    pub time: f64,    // tiempo del registro enviado, s
+}
+
+// Pseudo-terminal cuyo lado esclavo hace de puerto serie
+#[cfg(unix)]
+pub struct PseudoTerminal {
+    master: std::fs::File,
+    // Mantener el esclavo abierto evita errores en el maestro mientras la
+    // interfaz no está conectada
+    _slave: std::fs::File,
+    pub path: String,
+}
+
+#[cfg(unix)]
+impl PseudoTerminal {
+    pub fn open() -> Result<Self, String> {
+        use std::os::fd::FromRawFd;
+
+        let mut master = 0;
+        let mut slave = 0;
+        let mut name = [0 as libc::c_char; 128];
+        // SAFETY: los punteros apuntan a variables locales válidas y el
+        // buffer del nombre supera el largo máximo de /dev/pts/N
+        let result = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                name.as_mut_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+            )
+        };
+        if result != 0 {
+            return Err(format!(
+                "Error al crear el pseudo-terminal: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        // SAFETY: openpty devolvió descriptores abiertos que pasan a ser de
+        // los File; el nombre quedó terminado en nulo
+        let (master_file, slave_file, path) = unsafe {
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(slave, &mut termios) == 0 {
+                libc::cfmakeraw(&mut termios);
+                libc::tcsetattr(slave, libc::TCSANOW, &termios);
+            }
+            // Como la placa, el simulador no espera a que alguien lea
+            let flags = libc::fcntl(master, libc::F_GETFL);
+            libc::fcntl(master, libc::F_SETFL, flags | libc::O_NONBLOCK);
+            (
+                std::fs::File::from_raw_fd(master),
+                std::fs::File::from_raw_fd(slave),
+                std::ffi::CStr::from_ptr(name.as_ptr())
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        };
+
+        Ok(Self {
+            master: master_file,
+            _slave: slave_file,
+            path,
+        })
+    }
+
+    // Envía un registro completo; devuelve false si se descartó entero por
+    // tener el buffer lleno. Si ya salió una parte, se espera a que haya
+    // lugar para el resto, para no cortar el registro a la mitad (la placa
+    // nunca envía tramas partidas); solo se abandona si se pide detener
+    fn send(&mut self, bytes: &[u8], running: &Arc<Mutex<bool>>) -> Result<bool, String> {
+        use std::io::Write;
+        use std::os::fd::AsRawFd;
+
+        let mut written = 0;
+        while written < bytes.len() {
+            match self.master.write(&bytes[written..]) {
+                Ok(n) => written += n,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    if written == 0 {
+                        return Ok(false);
+                    }
+                    if !*running.lock().unwrap() {
+                        return Ok(true);
+                    }
+                    let mut fd = libc::pollfd {
+                        fd: self.master.as_raw_fd(),
+                        events: libc::POLLOUT,
+                        revents: 0,
+                    };
+                    // SAFETY: `fd` es un pollfd válido durante la llamada y
+                    // el descriptor sigue abierto mientras viva `master`
+                    unsafe { libc::poll(&mut fd, 1, 50) };
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(format!("Error al escribir en el pseudo-terminal: {}", e)),
+            }
+        }
+        Ok(true)
+    }
+}
+
+// Transmite los registros respetando sus tiempos hasta terminar o hasta que
+// `running` pase a false
+#[cfg(unix)]
+pub fn run(
+    pty: &mut PseudoTerminal,
+    schema: &Schema,
+    config: &SimulatorConfig,
+    running: &Arc<Mutex<bool>>,
+    stats: &Arc<Mutex<SimulatorStats>>,
+) -> Result<(), String> {
+    let first_time = match config.points.first() {
+        Some(point) => point.time,
+        None => return Err("No hay registros para simular".to_string()),
+    };
+    let mut sequence: u16 = 0;
+
+    loop {
+        let start = Instant::now();
+        for point in &config.points {
+            let due = Duration::from_secs_f64((point.time - first_time).max(0.0) / config.speed);
+            while start.elapsed() < due {
+                if !*running.lock().unwrap() {
+                    return Ok(());
+                }
+                let remaining = due - start.elapsed().min(due);
+                std::thread::sleep(remaining.min(Duration::from_millis(50)));
+            }
+            if !*running.lock().unwrap() {
+                return Ok(());
+            }
+
+            let bytes = encode_point(schema, config.wire_format, sequence, point)?;
+            let sent = pty.send(&bytes, running)?;
+            sequence = sequence.wrapping_add(1);
+
+            let mut stats = stats.lock().unwrap();
+            if sent {
+                stats.sent += 1;
+            } else {
+                stats.dropped += 1;
+            }
+            stats.time = point.time;
+        }
+
+        if !config.repeat {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,726 @@
+// Fuentes de datos de la adquisición.
+//
+// Todas entregan bytes en el formato del cable, así que el decodificador,
+// la calibración y el registro son los mismos para cualquier fuente:
+//
+//   - Serie: el puerto de la placa.
+//   - TCP cliente: conexión a un controlador en la red (p. ej. un ESP32);
+//     si se corta, se reintenta cada segundo.
+//   - TCP servidor: el controlador se conecta a Apogeo; al desconectarse se
+//     espera al siguiente.
+//   - UDP: paquetes recibidos en un puerto local.
+//   - Reproducción: un registro CSV transmitido con sus tiempos.
+//   - Captura: los bytes crudos de un ensayo anterior, para reprocesarlo.
+//   - Sintética: una curva de empuje generada, para ensayar sin banco.
+
+use crate::capture::{self, CaptureSession, Record};
+use crate::dataset::{self, DataPoint};
+use crate::framing::WireFormat;
+use crate::schema::Schema;
+use crate::simulator::{self, SyntheticCurve};
+use chrono::{DateTime, Utc};
+use std::io::Read;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+// Espera máxima de una lectura, para revisar a tiempo si hay que detenerse
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+// Pausa entre intentos de reconexión TCP
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+pub trait DataSource: Send {
+    // Copia en `buf` los bytes recibidos. Ok(Some(0)) si no llegó nada
+    // dentro de la espera; Ok(None) si la fuente terminó
+    fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, String>;
+
+    // Estado del enlace y contadores, para el panel de estado
+    fn status(&self) -> &SourceStatus;
+
+    // Solo las redefine una captura que se reprocesa: inicio de la sesión
+    // original, su cabecera, hora a la que llegó lo último leído (desde el
+    // inicio) y ventana de una tara pedida en ese punto
+    fn started_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+
+    fn recorded_header(&self) -> &[String] {
+        &[]
+    }
+
+    fn received_at(&self) -> Option<Duration> {
+        None
+    }
+
+    fn recorded_tare(&mut self) -> Option<f64> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LinkState {
+    Connecting,
+    Connected,
+    Listening, // esperando un cliente TCP o paquetes UDP
+    Disconnected,
+    Finished,
+}
+
+impl LinkState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LinkState::Connecting => "Conectando",
+            LinkState::Connected => "Conectado",
+            LinkState::Listening => "Escuchando",
+            LinkState::Disconnected => "Desconectado, reintentando",
+            LinkState::Finished => "Terminado",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SourceStatus {
+    pub state: LinkState,
+    pub peer: String, // dispositivo conectado o último remitente
+    pub packets: u64, // lecturas con datos (datagramas en UDP)
+    pub bytes: u64,
+    pub reconnects: u64,
+    pub last_received: Option<Instant>,
+    pub last_error: String,
+}
+
+impl Default for SourceStatus {
+    fn default() -> Self {
+        Self {
+            state: LinkState::Connecting,
+            peer: String::new(),
+            packets: 0,
+            bytes: 0,
+            reconnects: 0,
+            last_received: None,
+            last_error: String::new(),
+        }
+    }
+}
+
+impl SourceStatus {
+    fn connected(peer: String) -> Self {
+        Self {
+            state: LinkState::Connected,
+            peer,
+            ..Self::default()
+        }
+    }
+
+    fn received(&mut self, n: usize) {
+        if n > 0 {
+            self.packets += 1;
+            self.bytes += n as u64;
+            self.last_received = Some(Instant::now());
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SourceKind {
+    Serial,
+    Tcp,
+    TcpServer,
+    Udp,
+    Replay,
+    Synthetic,
+    Capture,
+}
+
+impl SourceKind {
+    pub const ALL: [SourceKind; 7] = [
+        SourceKind::Serial,
+        SourceKind::Tcp,
+        SourceKind::TcpServer,
+        SourceKind::Udp,
+        SourceKind::Replay,
+        SourceKind::Synthetic,
+        SourceKind::Capture,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SourceKind::Serial => "Puerto serie",
+            SourceKind::Tcp => "TCP (cliente)",
+            SourceKind::TcpServer => "TCP (servidor)",
+            SourceKind::Udp => "UDP",
+            SourceKind::Replay => "Reproducir registro",
+            SourceKind::Synthetic => "Curva sintética",
+            SourceKind::Capture => "Reprocesar captura",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SourceConfig {
+    pub kind: SourceKind,
+    pub port_name: String,
+    pub baud_rate: u32,
+    pub tcp_address: String, // host:puerto del controlador
+    pub tcp_listen: String,  // dirección local del servidor TCP
+    pub udp_address: String, // dirección local donde se escucha
+    pub replay_path: String,
+    pub capture_path: String,
+    pub capture_session: usize, // sesión de la captura, desde 1; 0 = la última
+    pub speed: f64,             // velocidad de la reproducción, la curva y la captura
+    pub repeat: bool,           // vuelve a empezar al terminar
+    pub synthetic: SyntheticCurve,
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        Self {
+            kind: SourceKind::Serial,
+            port_name: "COM9".to_string(),
+            baud_rate: 115200,
+            tcp_address: "192.168.4.1:3333".to_string(),
+            tcp_listen: "0.0.0.0:3333".to_string(),
+            udp_address: "0.0.0.0:3333".to_string(),
+            replay_path: "datos.csv".to_string(),
+            capture_path: "datos.cap".to_string(),
+            capture_session: 0,
+            speed: 1.0,
+            repeat: false,
+            synthetic: SyntheticCurve::default(),
+        }
+    }
+}
+
+impl SourceConfig {
+    // Descripción para el panel de estado y el registro
+    pub fn describe(&self) -> String {
+        match self.kind {
+            SourceKind::Serial => format!("serie {} a {} baudios", self.port_name, self.baud_rate),
+            SourceKind::Tcp => format!("TCP {}", self.tcp_address),
+            SourceKind::TcpServer => format!("servidor TCP {}", self.tcp_listen),
+            SourceKind::Udp => format!("UDP {}", self.udp_address),
+            SourceKind::Replay => format!("reproducción de {} (x{})", self.replay_path, self.speed),
+            SourceKind::Synthetic => format!(
+                "curva sintética ({} N, {} s, x{})",
+                self.synthetic.peak_thrust, self.synthetic.burn_time, self.speed
+            ),
+            SourceKind::Capture => {
+                let mut text = format!("captura {}", self.capture_path);
+                if self.capture_session > 0 {
+                    text.push_str(&format!(", sesión {}", self.capture_session));
+                }
+                // Al reprocesar de una vez la velocidad es infinita
+                if self.speed.is_finite() {
+                    text.push_str(&format!(" (x{})", self.speed));
+                }
+                text
+            }
+        }
+    }
+
+    // Puerto o dirección de la fuente para la cabecera del registro; la
+    // curva sintética no tiene
+    pub fn endpoint(&self) -> Option<String> {
+        match self.kind {
+            SourceKind::Serial => Some(self.port_name.clone()),
+            SourceKind::Tcp => Some(self.tcp_address.clone()),
+            SourceKind::TcpServer => Some(self.tcp_listen.clone()),
+            SourceKind::Udp => Some(self.udp_address.clone()),
+            SourceKind::Replay => Some(self.replay_path.clone()),
+            SourceKind::Capture => Some(self.capture_path.clone()),
+            SourceKind::Synthetic => None,
+        }
+    }
+
+    // Si están los datos que necesita la fuente elegida
+    pub fn is_complete(&self) -> bool {
+        match self.kind {
+            SourceKind::Serial => !self.port_name.is_empty(),
+            SourceKind::Tcp => !self.tcp_address.is_empty(),
+            SourceKind::TcpServer => !self.tcp_listen.is_empty(),
+            SourceKind::Udp => !self.udp_address.is_empty(),
+            SourceKind::Replay => !self.replay_path.is_empty(),
+            SourceKind::Capture => !self.capture_path.is_empty(),
+            SourceKind::Synthetic => true,
+        }
+    }
+
+    pub fn open(
+        &self,
+        schema: &Schema,
+        wire_format: WireFormat,
+    ) -> Result<Box<dyn DataSource>, String> {
+        match self.kind {
+            SourceKind::Serial => {
+                let port = serialport::new(&self.port_name, self.baud_rate)
+                    .timeout(READ_TIMEOUT)
+                    .open()
+                    .map_err(|e| format!("Error al abrir el puerto: {}", e))?;
+                Ok(Box::new(SerialSource {
+                    port,
+                    status: SourceStatus::connected(self.port_name.clone()),
+                }))
+            }
+            SourceKind::Tcp => {
+                let address = self
+                    .tcp_address
+                    .to_socket_addrs()
+                    .map_err(|e| format!("Dirección TCP inválida: {}", e))?
+                    .next()
+                    .ok_or("Dirección TCP inválida")?;
+                let mut source = TcpClientSource {
+                    address,
+                    stream: None,
+                    last_attempt: None,
+                    status: SourceStatus::default(),
+                };
+                source.connect();
+                Ok(Box::new(source))
+            }
+            SourceKind::TcpServer => {
+                let listener = TcpListener::bind(&self.tcp_listen)
+                    .map_err(|e| format!("Error al abrir el servidor TCP: {}", e))?;
+                listener
+                    .set_nonblocking(true)
+                    .map_err(|e| format!("Error al configurar el servidor TCP: {}", e))?;
+                Ok(Box::new(TcpServerSource {
+                    listener,
+                    stream: None,
+                    status: SourceStatus {
+                        state: LinkState::Listening,
+                        ..SourceStatus::default()
+                    },
+                }))
+            }
+            SourceKind::Udp => {
+                let socket = UdpSocket::bind(&self.udp_address)
+                    .map_err(|e| format!("Error al abrir el puerto UDP: {}", e))?;
+                socket
+                    .set_read_timeout(Some(READ_TIMEOUT))
+                    .map_err(|e| format!("Error al configurar el puerto UDP: {}", e))?;
+                Ok(Box::new(UdpSource {
+                    socket,
+                    pending: Pending::default(),
+                    status: SourceStatus {
+                        state: LinkState::Listening,
+                        ..SourceStatus::default()
+                    },
+                }))
+            }
+            SourceKind::Replay => {
+                let points = dataset::load(schema, &self.replay_path)?.data_points;
+                Ok(Box::new(ReplaySource::new(
+                    self.replay_path.clone(),
+                    points,
+                    schema,
+                    wire_format,
+                    self.speed,
+                    self.repeat,
+                )))
+            }
+            SourceKind::Synthetic => Ok(Box::new(ReplaySource::new(
+                "generador".to_string(),
+                self.synthetic.session(schema),
+                schema,
+                wire_format,
+                self.speed,
+                self.repeat,
+            ))),
+            SourceKind::Capture => {
+                let mut sessions = capture::read(&self.capture_path)?;
+                let count = sessions.len();
+                let index = match self.capture_session {
+                    0 => count - 1,
+                    n if n <= count => n - 1,
+                    n => {
+                        return Err(format!(
+                            "La captura tiene {} sesiones, no existe la {}",
+                            count, n
+                        ))
+                    }
+                };
+                Ok(Box::new(CaptureSource::new(
+                    self.capture_path.clone(),
+                    sessions.swap_remove(index),
+                    self.speed,
+                )))
+            }
+        }
+    }
+}
+
+// Lecturas que no retornan datos por la espera no son errores
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+    )
+}
+
+struct SerialSource {
+    port: Box<dyn serialport::SerialPort>,
+    status: SourceStatus,
+}
+
+impl DataSource for SerialSource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, String> {
+        match self.port.read(buf) {
+            Ok(n) => {
+                self.status.received(n);
+                Ok(Some(n))
+            }
+            Err(e) if is_timeout(&e) => Ok(Some(0)),
+            Err(e) => Err(format!("Error al leer el puerto: {}", e)),
+        }
+    }
+
+    fn status(&self) -> &SourceStatus {
+        &self.status
+    }
+}
+
+// Lee de una conexión TCP; None si se cortó
+fn read_stream(stream: &mut TcpStream, buf: &mut [u8], status: &mut SourceStatus) -> Option<usize> {
+    match stream.read(buf) {
+        Ok(0) => {
+            status.last_error = "El controlador cerró la conexión".to_string();
+            None
+        }
+        Ok(n) => {
+            status.received(n);
+            Some(n)
+        }
+        Err(e) if is_timeout(&e) => Some(0),
+        Err(e) => {
+            status.last_error = format!("Error al leer la conexión TCP: {}", e);
+            None
+        }
+    }
+}
+
+struct TcpClientSource {
+    address: SocketAddr,
+    stream: Option<TcpStream>,
+    last_attempt: Option<Instant>,
+    status: SourceStatus,
+}
+
+impl TcpClientSource {
+    fn connect(&mut self) {
+        self.last_attempt = Some(Instant::now());
+        let stream = TcpStream::connect_timeout(&self.address, CONNECT_TIMEOUT)
+            .and_then(|stream| stream.set_read_timeout(Some(READ_TIMEOUT)).map(|_| stream));
+        match stream {
+            Ok(stream) => {
+                if self.status.last_received.is_some() || self.status.reconnects > 0 {
+                    self.status.reconnects += 1;
+                }
+                self.status.state = LinkState::Connected;
+                self.status.peer = self.address.to_string();
+                self.stream = Some(stream);
+            }
+            Err(e) => {
+                self.status.state = LinkState::Disconnected;
+                self.status.last_error = format!("Error al conectar por TCP: {}", e);
+            }
+        }
+    }
+}
+
+impl DataSource for TcpClientSource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, String> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => {
+                let due = self
+                    .last_attempt
+                    .is_none_or(|t| t.elapsed() >= RECONNECT_INTERVAL);
+                if due {
+                    self.connect();
+                } else {
+                    std::thread::sleep(READ_TIMEOUT);
+                }
+                return Ok(Some(0));
+            }
+        };
+
+        match read_stream(stream, buf, &mut self.status) {
+            Some(n) => Ok(Some(n)),
+            None => {
+                self.stream = None;
+                self.status.state = LinkState::Disconnected;
+                Ok(Some(0))
+            }
+        }
+    }
+
+    fn status(&self) -> &SourceStatus {
+        &self.status
+    }
+}
+
+// Atiende a un controlador a la vez
+struct TcpServerSource {
+    listener: TcpListener,
+    stream: Option<TcpStream>,
+    status: SourceStatus,
+}
+
+impl DataSource for TcpServerSource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, String> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => {
+                match self.listener.accept() {
+                    Ok((stream, peer)) => {
+                        // El socket aceptado hereda el modo no bloqueante
+                        let configured = stream
+                            .set_nonblocking(false)
+                            .and_then(|_| stream.set_read_timeout(Some(READ_TIMEOUT)));
+                        match configured {
+                            Ok(()) => {
+                                if !self.status.peer.is_empty() {
+                                    self.status.reconnects += 1;
+                                }
+                                self.status.peer = peer.to_string();
+                                self.status.state = LinkState::Connected;
+                                self.stream = Some(stream);
+                            }
+                            Err(e) => {
+                                self.status.last_error =
+                                    format!("Error al configurar la conexión TCP: {}", e);
+                            }
+                        }
+                    }
+                    Err(e) if is_timeout(&e) => std::thread::sleep(READ_TIMEOUT),
+                    Err(e) => return Err(format!("Error en el servidor TCP: {}", e)),
+                }
+                return Ok(Some(0));
+            }
+        };
+
+        match read_stream(stream, buf, &mut self.status) {
+            Some(n) => Ok(Some(n)),
+            None => {
+                self.stream = None;
+                self.status.state = LinkState::Listening;
+                Ok(Some(0))
+            }
+        }
+    }
+
+    fn status(&self) -> &SourceStatus {
+        &self.status
+    }
+}
+
+// Bytes que no cupieron en la lectura anterior
+#[derive(Default)]
+struct Pending {
+    bytes: Vec<u8>,
+}
+
+impl Pending {
+    fn drain_into(&mut self, buf: &mut [u8]) -> usize {
+        let n = self.bytes.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.bytes[..n]);
+        self.bytes.drain(..n);
+        n
+    }
+}
+
+struct UdpSource {
+    socket: UdpSocket,
+    pending: Pending,
+    status: SourceStatus,
+}
+
+impl DataSource for UdpSource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, String> {
+        if self.pending.bytes.is_empty() {
+            // Un datagrama se recibe completo o se pierde el resto
+            let mut datagram = [0; 65536];
+            match self.socket.recv_from(&mut datagram) {
+                Ok((n, sender)) => {
+                    self.pending.bytes.extend_from_slice(&datagram[..n]);
+                    self.status.received(n);
+                    self.status.peer = sender.to_string();
+                    self.status.state = LinkState::Connected;
+                }
+                Err(e) if is_timeout(&e) => {
+                    // Sin paquetes por un rato se vuelve a mostrar "Escuchando"
+                    if self
+                        .status
+                        .last_received
+                        .is_some_and(|t| t.elapsed() > RECONNECT_INTERVAL)
+                    {
+                        self.status.state = LinkState::Listening;
+                    }
+                    return Ok(Some(0));
+                }
+                Err(e) => return Err(format!("Error al leer el puerto UDP: {}", e)),
+            }
+        }
+        Ok(Some(self.pending.drain_into(buf)))
+    }
+
+    fn status(&self) -> &SourceStatus {
+        &self.status
+    }
+}
+
+// Transmite registros respetando sus tiempos, como lo haría la placa
+struct ReplaySource {
+    points: Vec<DataPoint>,
+    schema: Schema,
+    wire_format: WireFormat,
+    speed: f64,
+    repeat: bool,
+    start: Instant,
+    index: usize,
+    sequence: u16,
+    pending: Pending,
+    status: SourceStatus,
+}
+
+impl ReplaySource {
+    fn new(
+        name: String,
+        points: Vec<DataPoint>,
+        schema: &Schema,
+        wire_format: WireFormat,
+        speed: f64,
+        repeat: bool,
+    ) -> Self {
+        Self {
+            points,
+            schema: schema.clone(),
+            wire_format,
+            speed: if speed > 0.0 { speed } else { 1.0 },
+            repeat,
+            start: Instant::now(),
+            index: 0,
+            sequence: 0,
+            pending: Pending::default(),
+            status: SourceStatus::connected(name),
+        }
+    }
+}
+
+impl DataSource for ReplaySource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, String> {
+        if self.pending.bytes.is_empty() {
+            if self.index >= self.points.len() {
+                if !self.repeat || self.points.is_empty() {
+                    self.status.state = LinkState::Finished;
+                    return Ok(None);
+                }
+                self.index = 0;
+                self.start = Instant::now();
+            }
+
+            let first_time = self.points[0].time;
+            let point = &self.points[self.index];
+            let due = Duration::from_secs_f64((point.time - first_time).max(0.0) / self.speed);
+            let elapsed = self.start.elapsed();
+            if elapsed < due {
+                std::thread::sleep((due - elapsed).min(READ_TIMEOUT));
+                return Ok(Some(0));
+            }
+
+            self.pending.bytes =
+                simulator::encode_point(&self.schema, self.wire_format, self.sequence, point)?;
+            self.sequence = self.sequence.wrapping_add(1);
+            self.index += 1;
+            self.status.received(self.pending.bytes.len());
+        }
+        Ok(Some(self.pending.drain_into(buf)))
+    }
+
+    fn status(&self) -> &SourceStatus {
+        &self.status
+    }
+}
+
+// Entrega las lecturas de una captura con sus tiempos originales, escalados
+// por la velocidad (infinita: sin pausas, para reprocesar de una vez)
+struct CaptureSource {
+    session: CaptureSession,
+    speed: f64,
+    start: Instant,
+    index: usize,
+    received_at: Duration,
+    tare: Option<f64>,
+    pending: Pending,
+    status: SourceStatus,
+}
+
+impl CaptureSource {
+    fn new(name: String, session: CaptureSession, speed: f64) -> Self {
+        Self {
+            session,
+            speed: if speed > 0.0 { speed } else { 1.0 },
+            start: Instant::now(),
+            index: 0,
+            received_at: Duration::ZERO,
+            tare: None,
+            pending: Pending::default(),
+            status: SourceStatus::connected(name),
+        }
+    }
+}
+
+impl DataSource for CaptureSource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, String> {
+        if self.pending.bytes.is_empty() {
+            let record = match self.session.records.get(self.index) {
+                Some(record) => record,
+                None => {
+                    self.status.state = LinkState::Finished;
+                    return Ok(None);
+                }
+            };
+
+            let due = Duration::from_secs_f64(record.time().as_secs_f64() / self.speed);
+            let elapsed = self.start.elapsed();
+            if elapsed < due {
+                std::thread::sleep((due - elapsed).min(READ_TIMEOUT));
+                return Ok(Some(0));
+            }
+
+            self.index += 1;
+            self.received_at = record.time();
+            match record {
+                Record::Data { bytes, .. } => {
+                    self.pending.bytes = bytes.clone();
+                    self.status.received(bytes.len());
+                }
+                Record::Tare { window, .. } => {
+                    self.tare = Some(*window);
+                    return Ok(Some(0));
+                }
+            }
+        }
+        Ok(Some(self.pending.drain_into(buf)))
+    }
+
+    fn status(&self) -> &SourceStatus {
+        &self.status
+    }
+
+    fn started_at(&self) -> Option<DateTime<Utc>> {
+        Some(self.session.started_at)
+    }
+
+    fn recorded_header(&self) -> &[String] {
+        &self.session.header
+    }
+
+    fn received_at(&self) -> Option<Duration> {
+        Some(self.received_at)
+    }
+
+    fn recorded_tare(&mut self) -> Option<f64> {
+        self.tare.take()
+    }
+}
@@ -0,0 +1,321 @@
+// Linealización de termopares con compensación de junta fría.
+//
+// La placa puede enviar el voltaje del termopar en milivolts (o en cuentas
+// de ADC con un factor `mv_por_cuenta`) en vez de grados. La temperatura se
+// obtiene con los polinomios NIST ITS-90:
+//
+//   E_total = E_medido + E(T_junta_fría)
+//   T = inversa(E_total)
+//
+// La junta fría es la temperatura ambiente del banco, en °C.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ThermocoupleType {
+    K,
+    J,
+    T,
+}
+
+// Tramo de un polinomio: válido entre `min` y `max` de su variable
+struct Range {
+    min: f64,
+    max: f64,
+    coefficients: &'static [f64],
+}
+
+// Tipo K, temperatura (°C) → voltaje (mV)
+const K_FORWARD: [Range; 2] = [
+    Range {
+        min: -270.0,
+        max: 0.0,
+        coefficients: &[
+            0.0,
+            0.394501280250e-01,
+            0.236223735980e-04,
+            -0.328589067840e-06,
+            -0.499048287770e-08,
+            -0.675090591730e-10,
+            -0.574103274280e-12,
+            -0.310888728940e-14,
+            -0.104516093650e-16,
+            -0.198892668780e-19,
+            -0.163226974860e-22,
+        ],
+    },
+    Range {
+        min: 0.0,
+        max: 1372.0,
+        coefficients: &[
+            -0.176004136860e-01,
+            0.389212049750e-01,
+            0.185587700320e-04,
+            -0.994575928740e-07,
+            0.318409457190e-09,
+            -0.560728448890e-12,
+            0.560750590590e-15,
+            -0.320207200030e-18,
+            0.971511471520e-22,
+            -0.121047212750e-25,
+        ],
+    },
+];
+
+// Término exponencial del tipo K sobre 0 °C
+const K_EXPONENTIAL: [f64; 3] = [0.118597600000e+00, -0.118343200000e-03, 0.126968600000e+03];
+
+// Tipo K, voltaje (mV) → temperatura (°C)
+const K_INVERSE: [Range; 3] = [
+    Range {
+        min: -5.891,
+        max: 0.0,
+        coefficients: &[
+            0.0,
+            2.5173462e+01,
+            -1.1662878e+00,
+            -1.0833638e+00,
+            -8.9773540e-01,
+            -3.7342377e-01,
+            -8.6632643e-02,
+            -1.0450598e-02,
+            -5.1920577e-04,
+        ],
+    },
+    Range {
+        min: 0.0,
+        max: 20.644,
+        coefficients: &[
+            0.0,
+            2.508355e+01,
+            7.860106e-02,
+            -2.503131e-01,
+            8.315270e-02,
+            -1.228034e-02,
+            9.804036e-04,
+            -4.413030e-05,
+            1.057734e-06,
+            -1.052755e-08,
+        ],
+    },
+    Range {
+        min: 20.644,
+        max: 54.886,
+        coefficients: &[
+            -1.318058e+02,
+            4.830222e+01,
+            -1.646031e+00,
+            5.464731e-02,
+            -9.650715e-04,
+            8.802193e-06,
+            -3.110810e-08,
+        ],
+    },
+];
+
+// Tipo J, temperatura (°C) → voltaje (mV)
+const J_FORWARD: [Range; 2] = [
+    Range {
+        min: -210.0,
+        max: 760.0,
+        coefficients: &[
+            0.0,
+            0.503811878150e-01,
+            0.304758369300e-04,
+            -0.856810657200e-07,
+            0.132281952950e-09,
+            -0.170529583370e-12,
+            0.209480906970e-15,
+            -0.125383953360e-18,
+            0.156317256970e-22,
+        ],
+    },
+    Range {
+        min: 760.0,
+        max: 1200.0,
+        coefficients: &[
+            0.296456256810e+03,
+            -0.149761277860e+01,
+            0.317871039240e-02,
+            -0.318476867010e-05,
+            0.157208190040e-08,
+            -0.306913690560e-12,
+        ],
+    },
+];
+
+// Tipo J, voltaje (mV) → temperatura (°C)
+const J_INVERSE: [Range; 3] = [
+    Range {
+        min: -8.095,
+        max: 0.0,
+        coefficients: &[
+            0.0,
+            1.9528268e+01,
+            -1.2286185e+00,
+            -1.0752178e+00,
+            -5.9086933e-01,
+            -1.7256713e-01,
+            -2.8131513e-02,
+            -2.3963370e-03,
+            -8.3823321e-05,
+        ],
+    },
+    Range {
+        min: 0.0,
+        max: 42.919,
+        coefficients: &[
+            0.0,
+            1.978425e+01,
+            -2.001204e-01,
+            1.036969e-02,
+            -2.549687e-04,
+            3.585153e-06,
+            -5.344285e-08,
+            5.099890e-10,
+        ],
+    },
+    Range {
+        min: 42.919,
+        max: 69.553,
+        coefficients: &[
+            -3.11358187e+03,
+            3.00543684e+02,
+            -9.94773230e+00,
+            1.70276630e-01,
+            -1.43033468e-03,
+            4.73886084e-06,
+        ],
+    },
+];
+
+// Tipo T, temperatura (°C) → voltaje (mV)
+const T_FORWARD: [Range; 2] = [
+    Range {
+        min: -270.0,
+        max: 0.0,
+        coefficients: &[
+            0.0,
+            0.387481063640e-01,
+            0.441944343470e-04,
+            0.118443231050e-06,
+            0.200329735540e-07,
+            0.901380195590e-09,
+            0.226511565930e-10,
+            0.360711542050e-12,
+            0.384939398830e-14,
+            0.282135219250e-16,
+            0.142515947790e-18,
+            0.487686622860e-21,
+            0.107955392700e-23,
+            0.139450270620e-26,
+            0.797951539270e-30,
+        ],
+    },
+    Range {
+        min: 0.0,
+        max: 400.0,
+        coefficients: &[
+            0.0,
+            0.387481063640e-01,
+            0.332922278800e-04,
+            0.206182434040e-06,
+            -0.218822568460e-08,
+            0.109968809280e-10,
+            -0.308157587720e-13,
+            0.454791352900e-16,
+            -0.275129016730e-19,
+        ],
+    },
+];
+
+// Tipo T, voltaje (mV) → temperatura (°C)
+const T_INVERSE: [Range; 2] = [
+    Range {
+        min: -5.603,
+        max: 0.0,
+        coefficients: &[
+            0.0,
+            2.5949192e+01,
+            -2.1316967e-01,
+            7.9018692e-01,
+            4.2527777e-01,
+            1.3304473e-01,
+            2.0241446e-02,
+            1.2668171e-03,
+        ],
+    },
+    Range {
+        min: 0.0,
+        max: 20.872,
+        coefficients: &[
+            0.0,
+            2.592800e+01,
+            -7.602961e-01,
+            4.637791e-02,
+            -2.165394e-03,
+            6.048144e-05,
+            -7.293422e-07,
+        ],
+    },
+];
+
+// Margen para que los extremos redondeados de las tablas sigan valiendo
+const EDGE_TOLERANCE: f64 = 1e-3;
+
+// Evalúa el tramo que contiene `x` (los tramos van en orden ascendente);
+// fuera de la tabla devuelve None
+fn evaluate(ranges: &[Range], x: f64) -> Option<f64> {
+    let first = ranges.first()?;
+    let last = ranges.last()?;
+    if x < first.min - EDGE_TOLERANCE || x > last.max + EDGE_TOLERANCE {
+        return None;
+    }
+    let range = ranges.iter().find(|r| x <= r.max).unwrap_or(last);
+    Some(
+        range
+            .coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |acc, c| acc * x + c),
+    )
+}
+
+impl ThermocoupleType {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThermocoupleType::K => "Tipo K",
+            ThermocoupleType::J => "Tipo J",
+            ThermocoupleType::T => "Tipo T",
+        }
+    }
+
+    // Voltaje (mV) que genera el termopar con la junta fría a 0 °C
+    pub fn millivolts(&self, celsius: f64) -> Option<f64> {
+        match self {
+            ThermocoupleType::K => {
+                let mut mv = evaluate(&K_FORWARD, celsius)?;
+                if celsius > 0.0 {
+                    let [a0, a1, a2] = K_EXPONENTIAL;
+                    mv += a0 * (a1 * (celsius - a2).powi(2)).exp();
+                }
+                Some(mv)
+            }
+            ThermocoupleType::J => evaluate(&J_FORWARD, celsius),
+            ThermocoupleType::T => evaluate(&T_FORWARD, celsius),
+        }
+    }
+
+    // Temperatura (°C) a partir del voltaje medido y la temperatura de la
+    // junta fría; NaN si queda fuera del rango de la tabla
+    pub fn celsius(&self, millivolts: f64, cold_junction: f64) -> f64 {
+        let inverse: &[Range] = match self {
+            ThermocoupleType::K => &K_INVERSE,
+            ThermocoupleType::J => &J_INVERSE,
+            ThermocoupleType::T => &T_INVERSE,
+        };
+        self.millivolts(cold_junction)
+            .and_then(|cj| evaluate(inverse, millivolts + cj))
+            .unwrap_or(f64::NAN)
+    }
+}